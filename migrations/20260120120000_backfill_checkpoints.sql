@@ -0,0 +1,15 @@
+-- Backfill checkpoints, one row per address walked with getSignaturesForAddress
+CREATE TABLE backfill_checkpoints (
+    address VARCHAR(44) PRIMARY KEY,
+    newest_signature VARCHAR(88),
+    oldest_signature VARCHAR(88),
+    completed BOOLEAN NOT NULL DEFAULT FALSE,
+    signatures_processed BIGINT NOT NULL DEFAULT 0,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TRIGGER update_backfill_checkpoints_updated_at
+    BEFORE UPDATE ON backfill_checkpoints
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
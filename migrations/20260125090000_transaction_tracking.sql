@@ -0,0 +1,5 @@
+-- Blockhash the transaction was signed with, used to detect expiry
+ALTER TABLE transaction_logs ADD COLUMN recent_blockhash VARCHAR(44);
+
+CREATE INDEX idx_transaction_logs_unsettled ON transaction_logs(created_at)
+    WHERE status IN ('pending', 'processed', 'confirmed');
@@ -0,0 +1,22 @@
+-- Durable nonce accounts owned by the admin key. A row is reserved while a
+-- transaction built with its nonce is waiting to be signed or to settle.
+CREATE TABLE nonce_accounts (
+    address VARCHAR(44) PRIMARY KEY,
+    authority VARCHAR(44) NOT NULL,
+    reserved_at TIMESTAMP WITH TIME ZONE,
+    signature VARCHAR(88),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_nonce_accounts_available ON nonce_accounts(updated_at)
+    WHERE reserved_at IS NULL;
+
+CREATE TRIGGER update_nonce_accounts_updated_at
+    BEFORE UPDATE ON nonce_accounts
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- Nonce account a durable transaction advances; its expiry is tracked by
+-- the nonce value instead of the blockhash
+ALTER TABLE transaction_logs ADD COLUMN nonce_account VARCHAR(44);
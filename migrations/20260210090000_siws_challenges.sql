@@ -0,0 +1,12 @@
+-- Sign-In-With-Solana challenges. Each nonce can be redeemed once, by the
+-- wallet it was issued to, before it expires.
+CREATE TABLE siws_challenges (
+    nonce VARCHAR(32) PRIMARY KEY,
+    wallet VARCHAR(44) NOT NULL,
+    message TEXT NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_siws_challenges_expires_at ON siws_challenges(expires_at);
@@ -0,0 +1,6 @@
+-- Keyset pagination and filters for the vault listing. Each sort column is
+-- paired with the id used as tiebreaker.
+CREATE INDEX idx_vaults_created_at_id ON vaults(created_at, id);
+CREATE INDEX idx_vaults_total_balance_id ON vaults(total_balance, id);
+CREATE INDEX idx_vaults_locked_balance_id ON vaults(locked_balance, id);
+CREATE INDEX idx_vaults_token_mint_created_at ON vaults(token_mint, created_at);
@@ -0,0 +1,10 @@
+-- Keyset pagination over event history, per vault and globally
+CREATE INDEX idx_vault_events_owner_created_at_id ON vault_events(vault_owner, created_at DESC, id DESC);
+CREATE INDEX idx_vault_events_created_at_id ON vault_events(created_at DESC, id DESC);
+
+-- Lookups by transaction signature, also used by the indexer's duplicate check
+CREATE INDEX idx_vault_events_signature ON vault_events((data->>'signature'));
+
+-- Superseded by the composite indexes above
+DROP INDEX IF EXISTS idx_vault_events_vault_owner;
+DROP INDEX IF EXISTS idx_vault_events_created_at;
@@ -0,0 +1,17 @@
+-- Notify stream listeners of new vault events. The payload is only the id;
+-- listeners read the row, keeping notifications under the payload limit.
+CREATE OR REPLACE FUNCTION notify_vault_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('vault_events', NEW.id::text);
+    RETURN NEW;
+END;
+$$ language 'plpgsql';
+
+CREATE TRIGGER notify_vault_events_insert
+    AFTER INSERT ON vault_events
+    FOR EACH ROW
+    EXECUTE FUNCTION notify_vault_event();
+
+-- Stream filter on the program behind lock, unlock and transfer events
+CREATE INDEX idx_vault_events_caller_program ON vault_events((data->>'caller_program'));
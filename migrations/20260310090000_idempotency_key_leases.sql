@@ -0,0 +1,8 @@
+-- A claim on an idempotency key is a lease the processing instance renews.
+-- An unfinished row whose lease ran out belongs to a crashed instance and
+-- can be claimed again.
+ALTER TABLE idempotency_keys ADD COLUMN locked_until TIMESTAMP WITH TIME ZONE;
+
+-- Cleanup of completed and abandoned keys
+CREATE INDEX idx_idempotency_keys_locked_until ON idempotency_keys(locked_until)
+    WHERE completed_at IS NULL;
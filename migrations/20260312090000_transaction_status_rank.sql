@@ -0,0 +1,20 @@
+-- Order of transaction statuses. Upserts never move a row to a lower rank,
+-- so a late or stale report can't undo a newer one.
+CREATE OR REPLACE FUNCTION transaction_status_rank(status VARCHAR)
+RETURNS INTEGER AS $$
+    SELECT CASE status
+        WHEN 'pending' THEN 0
+        WHEN 'expired' THEN 1
+        WHEN 'processed' THEN 2
+        WHEN 'confirmed' THEN 3
+        WHEN 'finalized' THEN 4
+        WHEN 'failed' THEN 4
+        ELSE 0
+    END
+$$ LANGUAGE SQL IMMUTABLE;
+
+-- The tracker polls the rows it looked at least recently first, so rows
+-- that never get a status don't hold up the rest
+DROP INDEX idx_transaction_logs_unsettled;
+CREATE INDEX idx_transaction_logs_unsettled ON transaction_logs(updated_at)
+    WHERE status IN ('pending', 'processed', 'confirmed');
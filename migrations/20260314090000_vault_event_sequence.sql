@@ -0,0 +1,6 @@
+-- Insertion order of vault events. Timestamps can tie or go backwards
+-- (indexed events carry their block time), so the event stream uses this as
+-- the SSE event id and resumes after it.
+ALTER TABLE vault_events ADD COLUMN seq BIGSERIAL;
+
+CREATE UNIQUE INDEX idx_vault_events_seq ON vault_events(seq);
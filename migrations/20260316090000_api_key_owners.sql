@@ -0,0 +1,3 @@
+-- Vault owners a partner key may act for. Keys with the `admin` scope act
+-- for every owner; other keys only for the owners listed here.
+ALTER TABLE api_keys ADD COLUMN owners TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,7 @@
+-- Address lookup tables created by the service. The newest one is loaded at
+-- startup unless LOOKUP_TABLE_ADDRESS names another.
+CREATE TABLE lookup_tables (
+    address VARCHAR(44) PRIMARY KEY,
+    authority VARCHAR(44) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
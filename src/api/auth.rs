@@ -0,0 +1,560 @@
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, State},
+    http::{header::{AUTHORIZATION, USER_AGENT}, request::Parts, Extensions, HeaderMap, Method},
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::JwkSet,
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use serde::{Deserialize, Serialize, Serializer};
+use solana_sdk::pubkey::Pubkey;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::database::ApiKey;
+use crate::services::api_keys::{scopes, ApiKeyService};
+use crate::utils::error::ApiError;
+
+/// Claims expected in every bearer token. `sub` is the caller's wallet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    pub exp: u64,
+    #[serde(default)]
+    pub iat: Option<u64>,
+    #[serde(default)]
+    pub iss: Option<String>,
+}
+
+/// Staff roles for the admin API, lowest first. Each role includes the
+/// permissions of the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    /// Read-only access to admin state.
+    Viewer,
+    /// Runs maintenance: backfills, lookup tables, nonce accounts.
+    Operator,
+    /// Manages which programs may move collateral.
+    RiskAdmin,
+    /// Initializes the authority and manages API keys.
+    SuperAdmin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::RiskAdmin => "risk-admin",
+            Role::SuperAdmin => "super-admin",
+        }
+    }
+
+    pub fn parse(role: &str) -> Option<Self> {
+        match role {
+            "viewer" => Some(Role::Viewer),
+            "operator" => Some(Role::Operator),
+            "risk-admin" => Some(Role::RiskAdmin),
+            "super-admin" => Some(Role::SuperAdmin),
+            _ => None,
+        }
+    }
+}
+
+/// The authenticated caller, available to any handler behind `auth_middleware`.
+/// Wallet sessions carry `wallet`; partner API keys carry `api_key_id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthUser {
+    #[serde(serialize_with = "serialize_wallet")]
+    pub wallet: Option<Pubkey>,
+    pub api_key_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub roles: Vec<String>,
+    pub scopes: Vec<String>,
+    /// Owners an API key may act for, see `authorize_owner`.
+    pub owners: Vec<String>,
+    pub expires_at: Option<u64>,
+}
+
+/// Writes the wallet as base58, the way clients send it, instead of bytes.
+fn serialize_wallet<S: Serializer>(wallet: &Option<Pubkey>, serializer: S) -> Result<S::Ok, S::Error> {
+    wallet.map(|wallet| wallet.to_string()).serialize(serializer)
+}
+
+impl AuthUser {
+    fn from_claims(wallet: Pubkey, claims: Claims) -> Self {
+        // Wallet sessions get the vault scopes; risk admins may also move collateral
+        let mut scopes = vec![scopes::VAULT_READ.to_string(), scopes::VAULT_WRITE.to_string()];
+        if claims.roles.iter().filter_map(|r| Role::parse(r)).any(|r| r >= Role::RiskAdmin) {
+            scopes.push(scopes::COLLATERAL_LOCK.to_string());
+        }
+
+        Self {
+            wallet: Some(wallet),
+            api_key_id: None,
+            name: None,
+            roles: claims.roles,
+            scopes,
+            owners: Vec::new(),
+            expires_at: Some(claims.exp),
+        }
+    }
+
+    fn from_api_key(key: ApiKey) -> Self {
+        Self {
+            wallet: None,
+            api_key_id: Some(key.id),
+            name: Some(key.name),
+            roles: Vec::new(),
+            scopes: key.scopes,
+            owners: key.owners,
+            expires_at: key.expires_at.map(|at| at.timestamp() as u64),
+        }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope || s == scopes::ADMIN)
+    }
+
+    /// Highest staff role held. API keys with the `admin` scope act as super-admins.
+    pub fn role(&self) -> Option<Role> {
+        if self.api_key_id.is_some() {
+            return self.has_scope(scopes::ADMIN).then_some(Role::SuperAdmin);
+        }
+
+        self.roles.iter().filter_map(|r| Role::parse(r)).max()
+    }
+
+    pub fn require_role(&self, role: Role) -> Result<(), ApiError> {
+        match self.role() {
+            Some(held) if held >= role => Ok(()),
+            _ => Err(ApiError::Forbidden),
+        }
+    }
+
+    /// Identifies the caller in the audit trail.
+    pub fn actor(&self) -> String {
+        match (&self.wallet, &self.api_key_id) {
+            (Some(wallet), _) => wallet.to_string(),
+            (None, Some(id)) => format!("key:{}", id),
+            (None, None) => "unknown".to_string(),
+        }
+    }
+
+    /// Wallet callers may only act on their own vault unless they are operators.
+    /// API keys may only act for the owners they were issued for, unless they
+    /// have the `admin` scope.
+    pub fn authorize_owner(&self, owner: &str) -> Result<(), ApiError> {
+        let allowed = match (&self.wallet, &self.api_key_id) {
+            (Some(wallet), _) => {
+                wallet.to_string() == owner
+                    || self.role().map_or(false, |role| role >= Role::Operator)
+            }
+            (None, Some(_)) => {
+                self.has_scope(scopes::ADMIN) || self.owners.iter().any(|o| o == owner)
+            }
+            (None, None) => false,
+        };
+
+        if allowed {
+            Ok(())
+        } else {
+            Err(ApiError::Forbidden)
+        }
+    }
+
+    /// Owner a cross-vault read such as the event stream is limited to, or
+    /// `None` for every vault. Leaving `owner` out only widens the read for
+    /// staff; wallets get their own vault and partner keys are refused.
+    pub fn owner_filter(&self, owner: Option<String>) -> Result<Option<String>, ApiError> {
+        if let Some(owner) = owner {
+            self.authorize_owner(&owner)?;
+            return Ok(Some(owner));
+        }
+
+        if self.role().map_or(false, |role| role >= Role::Operator) {
+            return Ok(None);
+        }
+
+        match &self.wallet {
+            Some(wallet) => Ok(Some(wallet.to_string())),
+            None => Err(ApiError::Forbidden),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions
+            .get::<AuthUser>()
+            .cloned()
+            .ok_or(ApiError::Unauthorized)
+    }
+}
+
+/// Verifies HS256 tokens signed with `JWT_SECRET` and, when a JWKS file is
+/// configured, RS256 tokens signed by one of its keys.
+#[derive(Clone)]
+pub struct JwtAuth {
+    secret: Arc<DecodingKey>,
+    signing_key: Arc<EncodingKey>,
+    jwks: Option<Arc<JwkSet>>,
+    issuer: Option<String>,
+}
+
+impl JwtAuth {
+    pub fn new(secret: &str, jwks: Option<JwkSet>, issuer: Option<String>) -> Self {
+        Self {
+            secret: Arc::new(DecodingKey::from_secret(secret.as_bytes())),
+            signing_key: Arc::new(EncodingKey::from_secret(secret.as_bytes())),
+            jwks: jwks.map(Arc::new),
+            issuer,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let jwks = match &config.jwt_jwks_path {
+            Some(path) => Some(load_jwks(path)?),
+            None => None,
+        };
+
+        Ok(Self::new(&config.jwt_secret, jwks, config.jwt_issuer.clone()))
+    }
+
+    /// Issues an HS256 session token for `wallet`.
+    pub fn issue(&self, wallet: &Pubkey, roles: &[String], ttl: Duration) -> Result<(String, DateTime<Utc>)> {
+        let issued_at = Utc::now();
+        let expires_at = issued_at + chrono::Duration::from_std(ttl)?;
+
+        let claims = Claims {
+            sub: wallet.to_string(),
+            roles: roles.to_vec(),
+            exp: expires_at.timestamp() as u64,
+            iat: Some(issued_at.timestamp() as u64),
+            iss: self.issuer.clone(),
+        };
+
+        let token = encode(&Header::new(Algorithm::HS256), &claims, &self.signing_key)
+            .context("Failed to sign session token")?;
+
+        Ok((token, expires_at))
+    }
+
+    pub fn verify(&self, token: &str) -> Result<AuthUser, ApiError> {
+        let header = decode_header(token).map_err(|_| ApiError::Unauthorized)?;
+
+        let (key, algorithm) = match header.alg {
+            Algorithm::HS256 => (self.secret.as_ref().clone(), Algorithm::HS256),
+            Algorithm::RS256 => {
+                let jwks = self.jwks.as_ref().ok_or(ApiError::Unauthorized)?;
+                let jwk = match &header.kid {
+                    Some(kid) => jwks.find(kid),
+                    None if jwks.keys.len() == 1 => jwks.keys.first(),
+                    None => None,
+                }.ok_or(ApiError::Unauthorized)?;
+
+                let key = DecodingKey::from_jwk(jwk).map_err(|_| ApiError::Unauthorized)?;
+                (key, Algorithm::RS256)
+            }
+            _ => return Err(ApiError::Unauthorized),
+        };
+
+        let mut validation = Validation::new(algorithm);
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let claims = decode::<Claims>(token, &key, &validation)
+            .map_err(|e| {
+                tracing::debug!("Rejected bearer token: {}", e);
+                ApiError::Unauthorized
+            })?
+            .claims;
+
+        let wallet = Pubkey::from_str(&claims.sub).map_err(|_| ApiError::Unauthorized)?;
+
+        Ok(AuthUser::from_claims(wallet, claims))
+    }
+}
+
+fn load_jwks(path: &Path) -> Result<JwkSet> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read JWKS file {}", path.display()))?;
+
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse JWKS file {}", path.display()))
+}
+
+/// Everything `auth_middleware` needs to identify a caller.
+#[derive(Clone)]
+pub struct AuthState {
+    pub jwt: JwtAuth,
+    pub api_keys: ApiKeyService,
+}
+
+/// Accepts a partner key in `X-API-Key` or a bearer token in `Authorization`.
+pub async fn auth_middleware(
+    State(auth): State<AuthState>,
+    mut request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<impl IntoResponse, ApiError> {
+    // CORS preflights carry no credentials; the route group's CORS layer answers them
+    if request.method() == Method::OPTIONS {
+        return Ok(next.run(request).await);
+    }
+
+    let headers = request.headers();
+
+    let user = if let Some(api_key) = headers.get("X-API-Key").and_then(|value| value.to_str().ok()) {
+        let key = auth.api_keys
+            .resolve(api_key.trim())
+            .await?
+            .ok_or(ApiError::Unauthorized)?;
+        AuthUser::from_api_key(key)
+    } else {
+        let token = headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or(ApiError::Unauthorized)?;
+        auth.jwt.verify(token.trim())?
+    };
+
+    request.extensions_mut().insert(user);
+
+    Ok(next.run(request).await)
+}
+
+/// Route layer rejecting callers without `scope`. Must run inside `auth_middleware`.
+pub async fn require_scope(
+    State(scope): State<&'static str>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = request.extensions()
+        .get::<AuthUser>()
+        .ok_or(ApiError::Unauthorized)?;
+
+    if !user.has_scope(scope) {
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Route layer rejecting callers below `role`. Must run inside `auth_middleware`.
+pub async fn require_role(
+    State(role): State<Role>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<impl IntoResponse, ApiError> {
+    let user = request.extensions()
+        .get::<AuthUser>()
+        .ok_or(ApiError::Unauthorized)?;
+
+    user.require_role(role)?;
+
+    Ok(next.run(request).await)
+}
+
+/// Who made a request and from where, for the audit trail.
+#[derive(Debug, Clone)]
+pub struct AuditContext {
+    pub actor: String,
+    pub ip_address: Option<IpAddr>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for AuditContext {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let user = AuthUser::from_request_parts(parts, state).await?;
+
+        let ip_address = client_ip(&parts.headers, &parts.extensions);
+
+        let user_agent = parts.headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
+        Ok(Self {
+            actor: user.actor(),
+            ip_address,
+            user_agent,
+        })
+    }
+}
+
+/// Proxies allowed to set `X-Forwarded-For`, as addresses or CIDR ranges.
+/// Added to every request as an extension, see `client_ip`.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+
+impl TrustedProxies {
+    pub fn parse(entries: &[String]) -> Result<Self> {
+        let networks = entries
+            .iter()
+            .map(|entry| {
+                let (addr, prefix) = match entry.split_once('/') {
+                    Some((addr, prefix)) => (addr, Some(prefix)),
+                    None => (entry.as_str(), None),
+                };
+
+                let addr: IpAddr = addr.parse()
+                    .with_context(|| format!("Invalid trusted proxy {}", entry))?;
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                let prefix = match prefix {
+                    Some(prefix) => prefix.parse::<u8>()
+                        .ok()
+                        .filter(|prefix| *prefix <= max)
+                        .with_context(|| format!("Invalid prefix length in trusted proxy {}", entry))?,
+                    None => max,
+                };
+
+                Ok((addr, prefix))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self(networks))
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let ip = ip.to_canonical();
+
+        self.0.iter().any(|(network, prefix)| match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                u32::from(*network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                u128::from(*network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        })
+    }
+}
+
+/// The client address. `X-Forwarded-For` is only followed from a trusted
+/// proxy, and only up to the right-most hop that isn't one: anything to the
+/// left of it may have been written by the client itself.
+pub fn client_ip(headers: &HeaderMap, extensions: &Extensions) -> Option<IpAddr> {
+    let peer = extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())?;
+
+    let Some(trusted) = extensions.get::<TrustedProxies>() else {
+        return Some(peer);
+    };
+    if !trusted.contains(peer) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<&str> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+
+    let mut client = peer;
+    for hop in forwarded.iter().rev() {
+        // Stop at garbage rather than skip past it
+        let Ok(ip) = hop.parse::<IpAddr>() else {
+            break;
+        };
+
+        client = ip;
+        if !trusted.contains(ip) {
+            break;
+        }
+    }
+
+    Some(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_the_wallet_as_base58() {
+        let wallet = Pubkey::new_unique();
+        let user = AuthUser::from_claims(wallet, Claims {
+            sub: wallet.to_string(),
+            roles: vec!["user".to_string()],
+            exp: 1_700_000_000,
+            iat: None,
+            iss: None,
+        });
+
+        let json = serde_json::to_value(&user).unwrap();
+
+        assert_eq!(json["wallet"], wallet.to_string());
+    }
+
+    fn partner_key(scopes: &[&str], owners: &[&str]) -> AuthUser {
+        AuthUser {
+            wallet: None,
+            api_key_id: Some(Uuid::new_v4()),
+            name: Some("partner".to_string()),
+            roles: Vec::new(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            owners: owners.iter().map(|o| o.to_string()).collect(),
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn partner_keys_only_read_their_own_owners() {
+        let key = partner_key(&[scopes::VAULT_READ], &["alice"]);
+
+        assert!(matches!(key.owner_filter(None), Err(ApiError::Forbidden)));
+        assert!(matches!(key.owner_filter(Some("bob".to_string())), Err(ApiError::Forbidden)));
+        assert!(matches!(key.owner_filter(Some("alice".to_string())), Ok(Some(owner)) if owner == "alice"));
+    }
+
+    #[test]
+    fn only_staff_read_every_owner() {
+        let admin_key = partner_key(&[scopes::ADMIN], &[]);
+        assert!(matches!(admin_key.owner_filter(None), Ok(None)));
+
+        let wallet = Pubkey::new_unique();
+        let claims = |roles: &[&str]| Claims {
+            sub: wallet.to_string(),
+            roles: roles.iter().map(|r| r.to_string()).collect(),
+            exp: 1_700_000_000,
+            iat: None,
+            iss: None,
+        };
+
+        let user = AuthUser::from_claims(wallet, claims(&["user"]));
+        assert!(matches!(user.owner_filter(None), Ok(Some(owner)) if owner == wallet.to_string()));
+
+        let operator = AuthUser::from_claims(wallet, claims(&[Role::Operator.as_str()]));
+        assert!(matches!(operator.owner_filter(None), Ok(None)));
+    }
+}
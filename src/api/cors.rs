@@ -0,0 +1,140 @@
+use std::time::Duration;
+use axum::http::{
+    header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER},
+    HeaderName, HeaderValue, Method,
+};
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+const PREFLIGHT_MAX_AGE: Duration = Duration::from_secs(600);
+
+/// Origins allowed per route group, each taken from the config list.
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Public reads and anything without its own list.
+    pub default_origins: Vec<String>,
+    /// Routes that build or send transactions, and wallet sign-in.
+    pub transaction_origins: Vec<String>,
+    pub admin_origins: Vec<String>,
+    pub allow_credentials: bool,
+}
+
+/// One entry of an origin list: `*`, an exact origin, or a wildcard
+/// subdomain such as `https://*.example.com`.
+#[derive(Debug, Clone)]
+enum OriginPattern {
+    Any,
+    Exact(String),
+    Subdomain { scheme: String, suffix: String },
+}
+
+impl OriginPattern {
+    fn parse(origin: &str) -> Self {
+        let origin = origin.trim().trim_end_matches('/');
+        if origin == "*" {
+            return OriginPattern::Any;
+        }
+
+        match origin.split_once("://*.") {
+            Some((scheme, domain)) => OriginPattern::Subdomain {
+                scheme: format!("{}://", scheme),
+                suffix: format!(".{}", domain),
+            },
+            None => OriginPattern::Exact(origin.to_string()),
+        }
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            OriginPattern::Any => true,
+            OriginPattern::Exact(allowed) => allowed == origin,
+            OriginPattern::Subdomain { scheme, suffix } => origin
+                .strip_prefix(scheme.as_str())
+                .and_then(|host| host.strip_suffix(suffix.as_str()))
+                .map_or(false, is_hostname),
+        }
+    }
+}
+
+/// Whether the part matched by `*` is one or more DNS labels, so userinfo,
+/// ports or paths can't slip in before the allowed suffix.
+fn is_hostname(labels: &str) -> bool {
+    labels
+        .split('.')
+        .all(|label| !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+}
+
+/// Builds the CORS layer for a route group from its origin list.
+pub fn cors_layer(origins: &[String], allow_credentials: bool) -> CorsLayer {
+    let patterns: Vec<OriginPattern> = origins.iter().map(|o| OriginPattern::parse(o)).collect();
+
+    // `Config` refuses `*` together with credentials, so `any` is safe here
+    let allow_origin = if patterns.iter().any(|p| matches!(p, OriginPattern::Any)) {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+            origin.to_str().map_or(false, |origin| patterns.iter().any(|p| p.matches(origin)))
+        })
+    };
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+        .allow_headers([
+            AUTHORIZATION,
+            CONTENT_TYPE,
+            HeaderName::from_static("x-api-key"),
+            HeaderName::from_static("idempotency-key"),
+        ])
+        .expose_headers([
+            RETRY_AFTER,
+            HeaderName::from_static("idempotent-replayed"),
+            HeaderName::from_static("x-ratelimit-limit"),
+            HeaderName::from_static("x-ratelimit-remaining"),
+            HeaderName::from_static("x-ratelimit-reset"),
+        ])
+        .allow_credentials(allow_credentials)
+        .max_age(PREFLIGHT_MAX_AGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_origins_against_patterns() {
+        let cases = [
+            ("*", "https://anything.test", true),
+            ("https://app.example.com", "https://app.example.com", true),
+            ("https://app.example.com/", "https://app.example.com", true),
+            ("https://app.example.com", "http://app.example.com", false),
+            ("https://app.example.com", "https://app.example.com.evil.com", false),
+            ("https://*.example.com", "https://app.example.com", true),
+            ("https://*.example.com", "https://a.b.example.com", true),
+            ("https://*.example.com", "https://example.com", false),
+            ("https://*.example.com", "https://evilexample.com", false),
+            ("https://*.example.com", "https://app.example.com.evil.com", false),
+            ("https://*.example.com", "https://evil.com/.example.com", false),
+            ("https://*.example.com", "https://evil.com@app.example.com", false),
+            ("https://*.example.com", "https://evil.com:443.example.com", false),
+            ("https://*.example.com", "https://.example.com", false),
+            ("https://*.example.com", "http://app.example.com", false),
+            // Ports have to be spelled out in the pattern
+            ("https://app.example.com", "https://app.example.com:8443", false),
+            ("https://app.example.com:8443", "https://app.example.com:8443", true),
+            ("https://*.example.com", "https://app.example.com:8443", false),
+            ("https://*.example.com:8443", "https://app.example.com:8443", true),
+            ("https://*.example.com:8443", "https://app.example.com", false),
+            ("https://*.example.com:8443", "https://app.example.com:9443", false),
+        ];
+
+        for (pattern, origin, expected) in cases {
+            assert_eq!(
+                OriginPattern::parse(pattern).matches(origin),
+                expected,
+                "{} against {}",
+                origin,
+                pattern,
+            );
+        }
+    }
+}
@@ -1,22 +1,33 @@
 use axum::{
     extract::{State, Path, Query, Json},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{Response, IntoResponse, sse::Event},
     body::Body,
 };
 use serde::{Deserialize, Serialize};
 use std::convert::Infallible;
+use std::str::FromStr;
 use std::sync::Arc;
 use uuid::Uuid;
+use solana_sdk::pubkey::Pubkey;
 use validator::Validate;
 
 use crate::models::{
     requests::*,
     responses::*,
-    database::Vault as DbVault,
+    database::{ApiKey, Vault as DbVault},
 };
+use crate::api::auth::{AuditContext, AuthUser, Role};
 use crate::database::DatabasePool;
+use crate::database::events::{EventCursor, VaultEventFilter};
+use crate::database::vaults::{VaultCursor, VaultFilter, VaultNotFound, VaultPageStart};
 use crate::services::vault::VaultService;
+use crate::services::reconciler::Reconciler;
+use crate::services::backfill::Backfill;
+use crate::services::rpc::{FeeEstimate, MAX_FEE_ACCOUNTS};
+use crate::services::api_keys::{scopes, ApiKeyService};
+use crate::services::rpc_pool::EndpointStats;
+use crate::services::siws::SiwsService;
 use crate::utils::error::{ApiError, ResultExt};
 
 type ApiResult<T> = Result<Json<T>, ApiError>;
@@ -29,28 +40,60 @@ pub async fn health_check() -> impl IntoResponse {
     })
 }
 
+pub async fn siws_challenge(
+    State(siws): State<SiwsService>,
+    Json(request): Json<SiwsChallengeRequest>,
+) -> ApiResult<SiwsChallengeResponse> {
+    request.validate()?;
+    
+    let wallet = Pubkey::from_str(&request.wallet)
+        .map_err(|_| ApiError::BadRequest(format!("Invalid wallet: {}", request.wallet)))?;
+    
+    let challenge = siws.challenge(&wallet).await?;
+    
+    Ok(Json(challenge))
+}
+
+pub async fn siws_verify(
+    State(siws): State<SiwsService>,
+    Json(request): Json<SiwsVerifyRequest>,
+) -> ApiResult<SessionResponse> {
+    request.validate()?;
+    
+    let session = siws.verify(&request.message, &request.signature).await?;
+    
+    Ok(Json(session))
+}
+
+pub async fn get_current_user(user: AuthUser) -> ApiResult<AuthUser> {
+    Ok(Json(user))
+}
+
 pub async fn create_vault(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Json(request): Json<CreateVaultRequest>,
-) -> ApiResult<CreateVaultResponse> {
+) -> ApiResult<BuildTransactionResponse> {
     request.validate()?;
     
-    let result = vault_service.initialize_vault(
-        &request.owner,
-        &request.token_mint,
-    ).await?;
+    user.authorize_owner(&request.owner)?;
     
-    Ok(Json(CreateVaultResponse {
-        vault_address: result.vault_address,
-        transaction: result.transaction,
-        message: "Vault initialized successfully".to_string(),
-    }))
+    let parameters = BuildParameters::InitializeVault {
+        owner: request.owner,
+        token_mint: request.token_mint,
+    };
+    let result = vault_service.build_transaction(&parameters, None, false, None, None).await?;
+    
+    Ok(Json(result))
 }
 
 pub async fn get_vault(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(owner): Path<String>,
 ) -> ApiResult<VaultResponse> {
+    user.authorize_owner(&owner)?;
+    
     let vault_info = vault_service.get_vault_info(&owner).await?;
     
     Ok(Json(VaultResponse {
@@ -66,62 +109,189 @@ pub async fn get_vault(
     }))
 }
 
+pub async fn get_onchain_vault(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
+    Path(owner): Path<String>,
+) -> ApiResult<OnchainVaultResponse> {
+    user.authorize_owner(&owner)?;
+    
+    let onchain = vault_service.get_onchain_vault(&owner).await?;
+    
+    // The vault may exist on chain before it has been indexed
+    let vault_info = match vault_service.get_vault_info(&owner).await {
+        Ok(vault_info) => Some(vault_info),
+        Err(e) if e.is::<VaultNotFound>() => None,
+        Err(e) => return Err(e.into()),
+    };
+    let database = vault_info.map(|vault_info| VaultResponse {
+        owner: vault_info.owner,
+        vault_address: vault_info.vault_address,
+        total_balance: vault_info.total_balance,
+        locked_balance: vault_info.locked_balance,
+        available_balance: vault_info.available_balance,
+        total_deposited: vault_info.total_deposited,
+        total_withdrawn: vault_info.total_withdrawn,
+        created_at: vault_info.created_at,
+        token_mint: vault_info.token_mint,
+    });
+    
+    let in_sync = database.as_ref().map_or(false, |db| {
+        db.total_balance == onchain.total_balance
+            && db.locked_balance == onchain.locked_balance
+            && db.available_balance == onchain.available_balance
+    });
+    
+    Ok(Json(OnchainVaultResponse {
+        owner,
+        database,
+        onchain,
+        in_sync,
+    }))
+}
+
+pub async fn list_vaults(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    Query(query): Query<VaultListQuery>,
+) -> ApiResult<VaultListResponse> {
+    query.validate()?;
+    
+    let page = query.page.unwrap_or(1);
+    let page_size = query.page_size.unwrap_or(50);
+    
+    let start = match &query.cursor {
+        Some(cursor) => {
+            let cursor = VaultCursor::decode(cursor)
+                .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+            if cursor.sort_by != query.sort_by {
+                return Err(ApiError::BadRequest("Cursor was issued for a different sort_by".to_string()));
+            }
+            if cursor.order != query.order {
+                return Err(ApiError::BadRequest("Cursor was issued for a different order".to_string()));
+            }
+            VaultPageStart::After(cursor)
+        }
+        None => VaultPageStart::Offset((page as i64 - 1) * page_size as i64),
+    };
+    
+    let filter = VaultFilter {
+        token_mint: query.token_mint,
+        min_total_balance: query.min_total_balance,
+        max_total_balance: query.max_total_balance,
+        min_locked_balance: query.min_locked_balance,
+        max_locked_balance: query.max_locked_balance,
+        created_after: query.created_after,
+        created_before: query.created_before,
+    };
+    
+    let vaults = pool
+        .list_vaults_page(&filter, query.sort_by, query.order, &start, page_size as i64)
+        .await?;
+    let total = pool.count_vaults(&filter).await?;
+    
+    // A short page is the last one
+    let next_cursor = match vaults.last() {
+        Some(last) if vaults.len() == page_size as usize => Some(VaultCursor::after(last, query.sort_by, query.order).encode()),
+        _ => None,
+    };
+    
+    Ok(Json(VaultListResponse {
+        vaults: vaults.into_iter().map(vault_response).collect(),
+        total: total as usize,
+        page,
+        page_size,
+        next_cursor,
+    }))
+}
+
+pub async fn get_vault_events(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
+    Path(owner): Path<String>,
+    Query(query): Query<VaultEventQuery>,
+) -> ApiResult<VaultEventListResponse> {
+    user.authorize_owner(&owner)?;
+    
+    list_events(&pool, Some(owner), query).await.map(Json)
+}
+
+pub async fn list_all_events(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    Query(mut query): Query<VaultEventQuery>,
+) -> ApiResult<VaultEventListResponse> {
+    let owner = query.owner.take();
+    
+    list_events(&pool, owner, query).await.map(Json)
+}
+
 pub async fn deposit(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(owner): Path<String>,
     Json(request): Json<DepositRequest>,
-) -> ApiResult<TransactionResponse> {
+) -> ApiResult<BuildTransactionResponse> {
     request.validate()?;
     
-    let result = vault_service.deposit_collateral(
-        &owner,
-        request.amount,
-        &request.user_token_account,
+    user.authorize_owner(&owner)?;
+    
+    let parameters = BuildParameters::Deposit {
+        owner,
+        amount: request.amount,
+        user_token_account: request.user_token_account,
+    };
+    let result = vault_service.build_transaction(
+        &parameters,
+        None,
+        false,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
-    Ok(Json(TransactionResponse {
-        transaction: result.transaction,
-        signature: result.signature,
-        estimated_fee: result.estimated_fee,
-        message: "Deposit transaction created".to_string(),
-    }))
+    Ok(Json(result))
 }
 
 pub async fn withdraw(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(owner): Path<String>,
     Json(request): Json<WithdrawRequest>,
-) -> ApiResult<TransactionResponse> {
+) -> ApiResult<BuildTransactionResponse> {
     request.validate()?;
     
-    let result = vault_service.withdraw_collateral(
-        &owner,
-        request.amount,
-        &request.user_token_account,
+    user.authorize_owner(&owner)?;
+    
+    let parameters = BuildParameters::Withdraw {
+        owner,
+        amount: request.amount,
+        user_token_account: request.user_token_account,
+    };
+    let result = vault_service.build_transaction(
+        &parameters,
+        None,
+        false,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
-    Ok(Json(TransactionResponse {
-        transaction: result.transaction,
-        signature: result.signature,
-        estimated_fee: result.estimated_fee,
-        message: "Withdrawal transaction created".to_string(),
-    }))
+    Ok(Json(result))
 }
 
 pub async fn lock_collateral(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(owner): Path<String>,
     Json(request): Json<LockRequest>,
 ) -> ApiResult<TransactionResponse> {
     request.validate()?;
     
+    user.authorize_owner(&owner)?;
+    
     let result = vault_service.lock_collateral(
         &owner,
         request.amount,
         &request.caller_program,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
     Ok(Json(TransactionResponse {
@@ -134,16 +304,20 @@ pub async fn lock_collateral(
 
 pub async fn unlock_collateral(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(owner): Path<String>,
     Json(request): Json<UnlockRequest>,
 ) -> ApiResult<TransactionResponse> {
     request.validate()?;
     
+    user.authorize_owner(&owner)?;
+    
     let result = vault_service.unlock_collateral(
         &owner,
         request.amount,
         &request.caller_program,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
     Ok(Json(TransactionResponse {
@@ -156,17 +330,21 @@ pub async fn unlock_collateral(
 
 pub async fn transfer_collateral(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(from_owner): Path<String>,
     Json(request): Json<TransferRequest>,
 ) -> ApiResult<TransactionResponse> {
     request.validate()?;
     
+    user.authorize_owner(&from_owner)?;
+    
     let result = vault_service.transfer_collateral(
         &from_owner,
         &request.to_owner,
         request.amount,
         &request.caller_program,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
     Ok(Json(TransactionResponse {
@@ -179,20 +357,20 @@ pub async fn transfer_collateral(
 
 pub async fn close_vault(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(owner): Path<String>,
-) -> ApiResult<TransactionResponse> {
-    let result = vault_service.close_vault(&owner).await?;
+) -> ApiResult<BuildTransactionResponse> {
+    user.authorize_owner(&owner)?;
     
-    Ok(Json(TransactionResponse {
-        transaction: result.transaction,
-        signature: result.signature,
-        estimated_fee: result.estimated_fee,
-        message: "Vault closed successfully".to_string(),
-    }))
+    let parameters = BuildParameters::CloseVault { owner };
+    let result = vault_service.build_transaction(&parameters, None, false, None, None).await?;
+    
+    Ok(Json(result))
 }
 
 pub async fn initialize_authority(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
     Json(request): Json<InitializeAuthorityRequest>,
 ) -> ApiResult<TransactionResponse> {
     request.validate()?;
@@ -200,8 +378,14 @@ pub async fn initialize_authority(
     let result = vault_service.initialize_authority(
         &request.authorized_programs,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
+    record_audit(&pool, &audit, "authority.initialize", None, None, Some(serde_json::json!({
+        "authorized_programs": request.authorized_programs,
+        "signature": result.signature,
+    }))).await;
+    
     Ok(Json(TransactionResponse {
         transaction: result.transaction,
         signature: result.signature,
@@ -212,15 +396,30 @@ pub async fn initialize_authority(
 
 pub async fn add_authorized_program(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
     Json(request): Json<AddAuthorizedProgramRequest>,
 ) -> ApiResult<TransactionResponse> {
     request.validate()?;
     
+    let before = authorized_programs(&vault_service).await;
+    
     let result = vault_service.add_authorized_program(
         &request.program,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
+    let mut after = before.clone().unwrap_or_default();
+    after.push(request.program.clone());
+    record_audit(
+        &pool,
+        &audit,
+        "authority.add_program",
+        Some(&request.program),
+        before.map(|programs| serde_json::json!({ "authorized_programs": programs })),
+        Some(serde_json::json!({ "authorized_programs": after, "signature": result.signature })),
+    ).await;
+    
     Ok(Json(TransactionResponse {
         transaction: result.transaction,
         signature: result.signature,
@@ -231,10 +430,26 @@ pub async fn add_authorized_program(
 
 pub async fn remove_authorized_program(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
     Path(program): Path<String>,
 ) -> ApiResult<TransactionResponse> {
+    let before = authorized_programs(&vault_service).await;
+    
     let result = vault_service.remove_authorized_program(&program).await?;
     
+    let after: Vec<String> = before.clone().unwrap_or_default()
+        .into_iter()
+        .filter(|p| *p != program)
+        .collect();
+    record_audit(
+        &pool,
+        &audit,
+        "authority.remove_program",
+        Some(&program),
+        before.map(|programs| serde_json::json!({ "authorized_programs": programs })),
+        Some(serde_json::json!({ "authorized_programs": after, "signature": result.signature })),
+    ).await;
+    
     Ok(Json(TransactionResponse {
         transaction: result.transaction,
         signature: result.signature,
@@ -243,31 +458,275 @@ pub async fn remove_authorized_program(
     }))
 }
 
+pub async fn list_api_keys(
+    State((pool, api_keys)): State<(DatabasePool, ApiKeyService)>,
+) -> ApiResult<Vec<ApiKey>> {
+    let keys = api_keys.list().await?;
+    
+    Ok(Json(keys))
+}
+
+pub async fn create_api_key(
+    State((pool, api_keys)): State<(DatabasePool, ApiKeyService)>,
+    audit: AuditContext,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> ApiResult<ApiKeySecretResponse> {
+    request.validate()?;
+    
+    if let Some(unknown) = request.scopes.iter().find(|s| !scopes::ALL.contains(&s.as_str())) {
+        return Err(ApiError::BadRequest(format!("Unknown scope: {}", unknown)));
+    }
+    
+    if let Some(invalid) = request.owners.iter().find(|o| Pubkey::from_str(o).is_err()) {
+        return Err(ApiError::BadRequest(format!("Invalid owner: {}", invalid)));
+    }
+    
+    let (api_key, key) = api_keys
+        .create(&request.name, &request.scopes, &request.owners, request.expires_at)
+        .await?;
+    
+    let target = api_key.id.to_string();
+    record_audit(&pool, &audit, "api_key.create", Some(&target), None, serde_json::to_value(&api_key).ok()).await;
+    
+    Ok(Json(ApiKeySecretResponse { api_key, key }))
+}
+
+pub async fn rotate_api_key(
+    State((pool, api_keys)): State<(DatabasePool, ApiKeyService)>,
+    audit: AuditContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult<ApiKeySecretResponse> {
+    let (api_key, key) = api_keys.rotate(id).await?.ok_or(ApiError::NotFound)?;
+    
+    let target = id.to_string();
+    record_audit(
+        &pool,
+        &audit,
+        "api_key.rotate",
+        Some(&target),
+        Some(serde_json::json!({ "id": id })),
+        serde_json::to_value(&api_key).ok(),
+    ).await;
+    
+    Ok(Json(ApiKeySecretResponse { api_key, key }))
+}
+
+pub async fn revoke_api_key(
+    State((pool, api_keys)): State<(DatabasePool, ApiKeyService)>,
+    audit: AuditContext,
+    Path(id): Path<Uuid>,
+) -> ApiResult<ApiKey> {
+    let api_key = api_keys.revoke(id).await?.ok_or(ApiError::NotFound)?;
+    
+    let target = id.to_string();
+    record_audit(&pool, &audit, "api_key.revoke", Some(&target), None, serde_json::to_value(&api_key).ok()).await;
+    
+    Ok(Json(api_key))
+}
+
+pub async fn list_nonce_accounts(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+) -> ApiResult<NonceAccountsResponse> {
+    let nonce_accounts = vault_service.list_nonce_accounts().await?;
+    let available = nonce_accounts.iter().filter(|n| n.reserved_at.is_none()).count();
+    
+    Ok(Json(NonceAccountsResponse {
+        nonce_accounts,
+        available,
+    }))
+}
+
+pub async fn create_nonce_accounts(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
+    Json(request): Json<CreateNonceAccountsRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    request.validate()?;
+    
+    let created = vault_service.create_nonce_accounts(request.count).await?;
+    
+    record_audit(&pool, &audit, "nonces.create", None, None, Some(serde_json::json!({
+        "nonce_accounts": created,
+    }))).await;
+    
+    let nonce_accounts = vault_service.list_nonce_accounts().await?;
+    let available = nonce_accounts.iter().filter(|n| n.reserved_at.is_none()).count();
+    
+    Ok((StatusCode::CREATED, Json(NonceAccountsResponse {
+        nonce_accounts,
+        available,
+    })))
+}
+
+pub async fn get_lookup_table(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+) -> ApiResult<LookupTableResponse> {
+    let table = vault_service.get_lookup_table().await?
+        .ok_or(ApiError::NotFound)?;
+    
+    Ok(Json(table))
+}
+
+pub async fn create_lookup_table(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, ApiError> {
+    let table = vault_service.create_lookup_table().await?;
+    
+    record_audit(&pool, &audit, "lookup_table.create", Some(&table.address), None, serde_json::to_value(&table).ok()).await;
+    
+    Ok((StatusCode::CREATED, Json(table)))
+}
+
+pub async fn extend_lookup_table(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
+    Json(request): Json<ExtendLookupTableRequest>,
+) -> ApiResult<LookupTableResponse> {
+    request.validate()?;
+    
+    let before = vault_service.get_lookup_table().await?
+        .ok_or(ApiError::NotFound)?;
+    
+    let table = vault_service.extend_lookup_table(&request.addresses).await?;
+    
+    record_audit(
+        &pool,
+        &audit,
+        "lookup_table.extend",
+        Some(&table.address),
+        Some(serde_json::json!({ "addresses": before.addresses })),
+        serde_json::to_value(&table).ok(),
+    ).await;
+    
+    Ok(Json(table))
+}
+
+pub async fn get_drift_report(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+) -> ApiResult<DriftReport> {
+    let report = Reconciler::new(pool, vault_service).drift_report().await?;
+    
+    Ok(Json(report))
+}
+
+pub async fn start_backfill(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    audit: AuditContext,
+) -> Result<impl IntoResponse, ApiError> {
+    // Take the lock here rather than in the task, so two requests can't
+    // both be accepted
+    let Some(lock) = pool.try_lock_backfill().await? else {
+        return Err(ApiError::Conflict("A backfill is already running".to_string()));
+    };
+    
+    record_audit(&pool, &audit, "backfill.start", None, None, None).await;
+    
+    let backfill = Backfill::new(
+        pool,
+        vault_service.rpc_service().clone(),
+        vault_service.program_id(),
+    );
+    
+    tokio::spawn(async move {
+        if let Err(e) = backfill.run_locked(lock).await {
+            tracing::error!("Backfill failed: {:?}", e);
+        }
+    });
+    
+    Ok((StatusCode::ACCEPTED, Json(serde_json::json!({
+        "message": "Backfill started",
+    }))))
+}
+
+pub async fn get_backfill_status(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+) -> ApiResult<BackfillStatusResponse> {
+    let checkpoints = pool.list_backfill_checkpoints().await?;
+    
+    Ok(Json(BackfillStatusResponse {
+        running: pool.is_backfill_running().await?,
+        checkpoints,
+    }))
+}
+
+pub async fn get_fee_estimates(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    Query(query): Query<FeeEstimateQuery>,
+) -> ApiResult<FeeEstimate> {
+    let accounts = query.accounts
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| Pubkey::from_str(a).map_err(|_| ApiError::BadRequest(format!("Invalid account: {}", a))))
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    if accounts.len() > MAX_FEE_ACCOUNTS {
+        return Err(ApiError::BadRequest(format!("At most {} accounts are allowed", MAX_FEE_ACCOUNTS)));
+    }
+    
+    let estimate = vault_service.rpc_service().estimate_priority_fees(&accounts).await?;
+    
+    Ok(Json(estimate))
+}
+
+pub async fn get_rpc_endpoints(
+    State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+) -> ApiResult<Vec<EndpointStats>> {
+    let stats = vault_service.rpc_service().endpoint_stats().await;
+    
+    Ok(Json(stats))
+}
+
 pub async fn build_transaction(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Path(tx_type): Path<String>,
     Json(request): Json<BuildTransactionRequest>,
-) -> ApiResult<TransactionResponse> {
+) -> ApiResult<BuildTransactionResponse> {
+    request.validate()?;
+    
+    let parameters = BuildParameters::parse(&tx_type, request.parameters)
+        .map_err(|e| ApiError::BadRequest(format!("Invalid {} parameters: {}", tx_type, e)))?;
+    
+    for owner in parameters.owners() {
+        user.authorize_owner(owner)?;
+    }
+    
+    // Durable builds reserve from a small pool of nonce accounts
+    if request.durable {
+        user.require_role(Role::Operator)?;
+    }
+    
+    // Only owner-signed instructions are built here; the service-signed ones,
+    // and their role checks, live on their own routes
     let result = vault_service.build_transaction(
-        &tx_type,
-        &request.parameters,
+        &parameters,
+        request.fee_payer.as_deref(),
+        request.durable,
         request.priority_fee,
+        request.priority_level,
     ).await?;
     
-    Ok(Json(TransactionResponse {
-        transaction: result.transaction,
-        signature: result.signature,
-        estimated_fee: result.estimated_fee,
-        message: "Transaction built".to_string(),
-    }))
+    Ok(Json(result))
 }
 
 pub async fn submit_transaction(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
+    user: AuthUser,
     Json(request): Json<SubmitTransactionRequest>,
 ) -> ApiResult<TransactionStatusResponse> {
+    request.validate()?;
+    
+    // Wallet sessions may only relay transactions their own wallet signed
+    let is_staff = user.role().map_or(false, |role| role >= Role::Operator);
+    let required_signer = user.wallet.filter(|_| !is_staff);
+    
     let result = vault_service.submit_transaction(
         &request.signed_transaction,
+        required_signer,
     ).await?;
     
     Ok(Json(TransactionStatusResponse {
@@ -298,27 +757,129 @@ pub async fn get_transaction_status(
 
 pub async fn stream_events(
     State((pool, vault_service)): State<(DatabasePool, VaultService)>,
-) -> impl IntoResponse {
-    let stream = vault_service.stream_events().await;
+    user: AuthUser,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> Result<impl IntoResponse, ApiError> {
+    query.validate()?;
+    
+    let owner = user.owner_filter(query.owner)?;
+    
+    // Sent back by EventSource when it reconnects
+    let last_event_id = match headers.get("Last-Event-ID") {
+        Some(id) => Some(
+            id.to_str().ok()
+                .and_then(|id| id.trim().parse::<i64>().ok())
+                .ok_or_else(|| ApiError::BadRequest("Invalid Last-Event-ID".to_string()))?,
+        ),
+        None => None,
+    };
+    
+    let filter = VaultEventFilter {
+        owner,
+        event_types: parse_event_types(query.event_type.as_deref())?,
+        caller_program: query.caller_program,
+        ..Default::default()
+    };
+    
+    let stream = vault_service.stream_events(filter, last_event_id).await?;
     
-    axum::response::Sse::new(stream)
-        .keep_alive(axum::response::sse::KeepAlive::default())
+    Ok(axum::response::Sse::new(stream)
+        .keep_alive(axum::response::sse::KeepAlive::default()))
 }
 
-pub async fn auth_middleware(
-    request: axum::extract::Request,
-    next: axum::middleware::Next,
-) -> Result<impl IntoResponse, ApiError> {
-    // Extract and validate API key or JWT token
-    let api_key = request.headers()
-        .get("X-API-Key")
-        .and_then(|value| value.to_str().ok())
-        .ok_or(ApiError::Unauthorized)?;
-    
-    // Validate API key (simplified)
-    if api_key != "your-secure-api-key" {
-        return Err(ApiError::Unauthorized);
+async fn list_events(
+    pool: &DatabasePool,
+    owner: Option<String>,
+    query: VaultEventQuery,
+) -> Result<VaultEventListResponse, ApiError> {
+    query.validate()?;
+    
+    let limit = query.limit.unwrap_or(50);
+    
+    let cursor = query.cursor
+        .as_deref()
+        .map(EventCursor::decode)
+        .transpose()
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    
+    let filter = VaultEventFilter {
+        owner,
+        event_types: parse_event_types(query.event_type.as_deref())?,
+        after: query.after,
+        before: query.before,
+        signature: query.signature,
+        caller_program: None,
+    };
+    
+    let events = pool.list_vault_events(&filter, cursor.as_ref(), limit as i64).await?;
+    
+    // A short page is the last one
+    let next_cursor = match events.last() {
+        Some(last) if events.len() == limit as usize => Some(EventCursor::after(last).encode()),
+        _ => None,
+    };
+    
+    Ok(VaultEventListResponse {
+        events,
+        next_cursor,
+    })
+}
+
+/// Parses a comma-separated `event_type` filter.
+fn parse_event_types(event_types: Option<&str>) -> Result<Vec<String>, ApiError> {
+    event_types
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .map(|t| VaultEventType::parse(t)
+            .map(|t| t.as_str().to_string())
+            .ok_or_else(|| ApiError::BadRequest(format!("Invalid event type: {}", t))))
+        .collect()
+}
+
+fn vault_response(vault: DbVault) -> VaultResponse {
+    VaultResponse {
+        owner: vault.owner,
+        vault_address: vault.vault_address,
+        total_balance: vault.total_balance as u64,
+        locked_balance: vault.locked_balance as u64,
+        available_balance: vault.available_balance as u64,
+        total_deposited: vault.total_deposited as u64,
+        total_withdrawn: vault.total_withdrawn as u64,
+        created_at: vault.created_at,
+        token_mint: vault.token_mint,
     }
+}
+
+/// Records an admin action. The action has already taken effect, so a failed
+/// write is logged rather than failing the request.
+async fn record_audit(
+    pool: &DatabasePool,
+    audit: &AuditContext,
+    action: &str,
+    target: Option<&str>,
+    old_values: Option<serde_json::Value>,
+    new_values: Option<serde_json::Value>,
+) {
+    let result = pool.insert_audit_entry(
+        action,
+        &audit.actor,
+        target,
+        old_values.as_ref(),
+        new_values.as_ref(),
+        audit.ip_address,
+        audit.user_agent.as_deref(),
+    ).await;
+    
+    if let Err(e) = result {
+        tracing::error!("Failed to record audit entry for {} by {}: {:?}", action, audit.actor, e);
+    }
+}
+
+async fn authorized_programs(vault_service: &VaultService) -> Option<Vec<String>> {
+    let authority = vault_service.get_onchain_authority().await.ok()?;
     
-    Ok(next.run(request).await)
-}
\ No newline at end of file
+    Some(authority.authorized_programs.iter().map(|program| program.to_string()).collect())
+}
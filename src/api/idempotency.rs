@@ -0,0 +1,211 @@
+use std::time::{Duration, Instant};
+use axum::{
+    body::{to_bytes, Body},
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use solana_sdk::hash::hashv;
+use tracing::{error, info, warn};
+
+use crate::api::auth::AuthUser;
+use crate::database::DatabasePool;
+use crate::utils::error::ApiError;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+const MAX_KEY_LEN: usize = 255;
+
+/// Largest request or response body kept for replay.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// How long a duplicate waits for the original request before giving up with 409.
+const IN_FLIGHT_WAIT: Duration = Duration::from_secs(10);
+const IN_FLIGHT_POLL: Duration = Duration::from_millis(200);
+
+/// A claim lapses this long after its last renewal, so a key held by an
+/// instance that died mid-request can be used again.
+const CLAIM_LEASE: Duration = Duration::from_secs(60);
+const CLAIM_RENEW_INTERVAL: Duration = Duration::from_secs(20);
+
+const COMPLETE_ATTEMPTS: u32 = 3;
+
+#[derive(Clone)]
+pub struct IdempotencyState {
+    pub db_pool: DatabasePool,
+    /// How long a stored response is replayed before the key can be reused.
+    pub ttl: Duration,
+}
+
+/// Replays the stored response for a repeated `Idempotency-Key`. A key reused
+/// with a different request gets 422; a duplicate of a request still being
+/// processed waits for it, then gets 409. Reads and requests without the header
+/// pass through.
+///
+/// The first request runs in its own task, so it finishes and stores its
+/// response even if the client disconnects.
+pub async fn idempotency(
+    State(state): State<IdempotencyState>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Result<Response, ApiError> {
+    if request.method().is_safe() {
+        return Ok(next.run(request).await);
+    }
+
+    let Some(key) = request.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(next.run(request).await);
+    };
+    let key = key.to_str()
+        .ok()
+        .map(str::trim)
+        .filter(|k| !k.is_empty() && k.len() <= MAX_KEY_LEN)
+        .ok_or_else(|| ApiError::BadRequest(format!("{} must be 1-{} characters", IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN)))?
+        .to_string();
+
+    let caller = request.extensions()
+        .get::<AuthUser>()
+        .ok_or(ApiError::Unauthorized)?
+        .actor();
+
+    let (parts, body) = request.into_parts();
+    let body = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|_| ApiError::BadRequest("Request body too large".to_string()))?;
+
+    let method = parts.method.as_str().to_string();
+    let path = parts.uri.path().to_string();
+    let request_hash = hashv(&[method.as_bytes(), path.as_bytes(), &body]).to_string();
+
+    let claimed = state.db_pool
+        .claim_idempotency_key(
+            &caller,
+            &key,
+            &method,
+            &path,
+            &request_hash,
+            state.ttl.as_secs() as i64,
+            CLAIM_LEASE.as_secs() as i64,
+        )
+        .await?;
+
+    if !claimed {
+        return replay(&state.db_pool, &caller, &key, &request_hash).await;
+    }
+
+    let request = axum::extract::Request::from_parts(parts, Body::from(body));
+    tokio::spawn(run_claimed(state.db_pool, caller, key, next, request))
+        .await
+        .map_err(|e| {
+            error!("Idempotent request task failed: {}", e);
+            ApiError::InternalServerError
+        })
+}
+
+/// Runs the request holding the claim on `key`, renewing the claim until the
+/// handler returns, then stores the response.
+async fn run_claimed(
+    db_pool: DatabasePool,
+    caller: String,
+    key: String,
+    next: axum::middleware::Next,
+    request: axum::extract::Request,
+) -> Response {
+    let handler = next.run(request);
+    tokio::pin!(handler);
+
+    let mut renew = tokio::time::interval_at(tokio::time::Instant::now() + CLAIM_RENEW_INTERVAL, CLAIM_RENEW_INTERVAL);
+    let response = loop {
+        tokio::select! {
+            response = &mut handler => break response,
+            _ = renew.tick() => {
+                if let Err(e) = db_pool.extend_idempotency_lease(&caller, &key, CLAIM_LEASE.as_secs() as i64).await {
+                    warn!("Failed to renew idempotency key {}: {:?}", key, e);
+                }
+            }
+        }
+    };
+
+    // Every outcome is kept, errors included: the request may have sent a
+    // transaction before failing, so it must not run again under this key
+    let (parts, body) = response.into_parts();
+    let (parts, body) = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => (parts, body),
+        Err(e) => {
+            error!("Failed to buffer response for idempotency key {}: {}", key, e);
+            let (parts, body) = ApiError::InternalServerError.into_response().into_parts();
+            (parts, to_bytes(body, MAX_BODY_BYTES).await.unwrap_or_default())
+        }
+    };
+
+    for attempt in 1..=COMPLETE_ATTEMPTS {
+        match db_pool.complete_idempotency_key(&caller, &key, parts.status.as_u16(), &body).await {
+            Ok(()) => return Response::from_parts(parts, Body::from(body)),
+            Err(e) => {
+                warn!("Failed to store response for idempotency key {} (attempt {}): {:?}", key, attempt, e);
+                tokio::time::sleep(IN_FLIGHT_POLL * attempt).await;
+            }
+        }
+    }
+
+    // Rather than have retries wait out the lease for a response that will
+    // never be stored
+    release(&db_pool, &caller, &key).await;
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+async fn release(db_pool: &DatabasePool, caller: &str, key: &str) {
+    match db_pool.release_idempotency_key(caller, key).await {
+        Ok(()) => info!("Released idempotency key {} without a stored response", key),
+        // It lapses with its lease instead
+        Err(e) => error!("Failed to release idempotency key {}: {:?}", key, e),
+    }
+}
+
+/// Deletes expired keys every `interval`.
+pub async fn run_cleanup(db_pool: DatabasePool, ttl: Duration, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        ticker.tick().await;
+
+        match db_pool.delete_expired_idempotency_keys(ttl.as_secs() as i64).await {
+            Ok(0) => {}
+            Ok(deleted) => info!("Deleted {} expired idempotency keys", deleted),
+            Err(e) => error!("Deleting expired idempotency keys failed: {:?}", e),
+        }
+    }
+}
+
+async fn replay(db_pool: &DatabasePool, caller: &str, key: &str, request_hash: &str) -> Result<Response, ApiError> {
+    let started = Instant::now();
+
+    loop {
+        // The row only disappears if the original request gave up its claim,
+        // or it expired and was reclaimed meanwhile
+        let stored = db_pool.get_idempotency_key(caller, key).await?
+            .ok_or_else(|| ApiError::Conflict("Idempotency key is being reused".to_string()))?;
+
+        if stored.request_hash != request_hash {
+            return Err(ApiError::UnprocessableEntity(
+                "Idempotency key was already used with a different request".to_string(),
+            ));
+        }
+
+        if let (Some(status), Some(body)) = (stored.status_code, stored.response_body) {
+            let status = StatusCode::from_u16(status as u16).unwrap_or(StatusCode::OK);
+            let mut response = (status, body).into_response();
+            response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            response.headers_mut().insert(REPLAYED_HEADER, HeaderValue::from_static("true"));
+            return Ok(response);
+        }
+
+        if started.elapsed() >= IN_FLIGHT_WAIT {
+            return Err(ApiError::Conflict("A request with this idempotency key is still in progress".to_string()));
+        }
+
+        tokio::time::sleep(IN_FLIGHT_POLL).await;
+    }
+}
@@ -1,2 +1,6 @@
+pub mod auth;
+pub mod cors;
 pub mod handlers;
+pub mod idempotency;
+pub mod rate_limit;
 pub mod routes;
\ No newline at end of file
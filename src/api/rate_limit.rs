@@ -0,0 +1,271 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use anyhow::Result;
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use redis::{aio::ConnectionManager, Script};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::api::auth::{client_ip, AuthUser};
+use crate::utils::error::ApiError;
+
+/// Drops old entries from a sorted-set window and admits the request if the
+/// window has room. Returns `{allowed, count, ms until the oldest entry expires}`.
+const SLIDING_WINDOW_SCRIPT: &str = r#"
+local key = KEYS[1]
+local now = tonumber(ARGV[1])
+local window = tonumber(ARGV[2])
+local limit = tonumber(ARGV[3])
+
+redis.call('ZREMRANGEBYSCORE', key, 0, now - window)
+local count = redis.call('ZCARD', key)
+local allowed = 0
+if count < limit then
+    redis.call('ZADD', key, now, ARGV[4])
+    count = count + 1
+    allowed = 1
+end
+redis.call('PEXPIRE', key, window)
+
+local reset = window
+local oldest = redis.call('ZRANGE', key, 0, 0, 'WITHSCORES')
+if oldest[2] then
+    reset = tonumber(oldest[2]) + window - now
+end
+return {allowed, count, reset}
+"#;
+
+/// In-memory windows are pruned once this many callers are tracked.
+const MAX_LOCAL_KEYS: usize = 10_000;
+
+/// Least time between attempts to reach Redis while it is down.
+const REDIS_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Budget a route group is limited by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteClass {
+    /// Lookups and admin reads.
+    Read,
+    /// Routes that build, sign or broadcast transactions.
+    Send,
+    /// Every request by client address, checked before authentication so
+    /// that guessing keys or tokens is limited too.
+    Ip,
+}
+
+impl RouteClass {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RouteClass::Read => "read",
+            RouteClass::Send => "send",
+            RouteClass::Ip => "ip",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub window: Duration,
+    pub read_requests: u64,
+    pub send_requests: u64,
+    pub ip_requests: u64,
+}
+
+struct Decision {
+    allowed: bool,
+    limit: u64,
+    remaining: u64,
+    reset_after: Duration,
+}
+
+/// Sliding-window limiter shared across instances through Redis. Falls back
+/// to per-instance windows while Redis is unreachable, and reconnects in the
+/// background if it wasn't reachable at startup.
+#[derive(Clone)]
+pub struct RateLimiter {
+    redis_url: String,
+    redis: Arc<RwLock<Option<ConnectionManager>>>,
+    connecting: Arc<AtomicBool>,
+    script: Arc<Script>,
+    local: Arc<Mutex<HashMap<String, VecDeque<Instant>>>>,
+    config: RateLimitConfig,
+}
+
+impl RateLimiter {
+    pub async fn new(redis_url: &str, config: RateLimitConfig) -> Self {
+        let redis = match connect(redis_url).await {
+            Ok(connection) => {
+                info!("Rate limiting through Redis at {}", redis_url);
+                Some(connection)
+            }
+            Err(e) => {
+                warn!("Redis unavailable, rate limiting per instance: {:?}", e);
+                None
+            }
+        };
+
+        Self {
+            redis_url: redis_url.to_string(),
+            redis: Arc::new(RwLock::new(redis)),
+            connecting: Arc::new(AtomicBool::new(false)),
+            script: Arc::new(Script::new(SLIDING_WINDOW_SCRIPT)),
+            local: Arc::new(Mutex::new(HashMap::new())),
+            config,
+        }
+    }
+
+    fn limit(&self, class: RouteClass) -> u64 {
+        match class {
+            RouteClass::Read => self.config.read_requests,
+            RouteClass::Send => self.config.send_requests,
+            RouteClass::Ip => self.config.ip_requests,
+        }
+    }
+
+    /// Connects to Redis in the background, at most once per
+    /// `REDIS_RETRY_DELAY`. `ConnectionManager` reconnects by itself once
+    /// it has connected, so this is only needed until the first success.
+    fn reconnect(&self) {
+        if self.connecting.swap(true, Ordering::AcqRel) {
+            return;
+        }
+
+        let limiter = self.clone();
+        tokio::spawn(async move {
+            match connect(&limiter.redis_url).await {
+                Ok(connection) => {
+                    info!("Rate limiting through Redis at {}", limiter.redis_url);
+                    *limiter.redis.write().await = Some(connection);
+                }
+                Err(e) => {
+                    warn!("Redis still unavailable, retrying in {:?}: {:?}", REDIS_RETRY_DELAY, e);
+                    tokio::time::sleep(REDIS_RETRY_DELAY).await;
+                }
+            }
+
+            limiter.connecting.store(false, Ordering::Release);
+        });
+    }
+
+    async fn check(&self, class: RouteClass, caller: &str) -> Decision {
+        let key = format!("ratelimit:{}:{}", class.as_str(), caller);
+        let limit = self.limit(class);
+
+        let redis = self.redis.read().await.clone();
+        match redis {
+            Some(redis) => match self.check_redis(redis, &key, limit).await {
+                Ok(decision) => return decision,
+                Err(e) => warn!("Redis rate limit check failed, using local window: {}", e),
+            },
+            None => self.reconnect(),
+        }
+
+        self.check_local(key, limit).await
+    }
+
+    async fn check_redis(&self, mut redis: ConnectionManager, key: &str, limit: u64) -> Result<Decision> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        let window = self.config.window.as_millis() as u64;
+
+        let (allowed, count, reset): (u64, u64, u64) = self.script
+            .key(key)
+            .arg(now)
+            .arg(window)
+            .arg(limit)
+            .arg(format!("{}-{}", now, Uuid::new_v4().simple()))
+            .invoke_async(&mut redis)
+            .await?;
+
+        Ok(Decision {
+            allowed: allowed == 1,
+            limit,
+            remaining: limit.saturating_sub(count),
+            reset_after: Duration::from_millis(reset),
+        })
+    }
+
+    async fn check_local(&self, key: String, limit: u64) -> Decision {
+        let now = Instant::now();
+        let window = self.config.window;
+        let mut local = self.local.lock().await;
+
+        if local.len() >= MAX_LOCAL_KEYS {
+            local.retain(|_, hits| hits.back().map_or(false, |last| now.duration_since(*last) < window));
+        }
+
+        let hits = local.entry(key).or_default();
+        while hits.front().map_or(false, |first| now.duration_since(*first) >= window) {
+            hits.pop_front();
+        }
+
+        let allowed = (hits.len() as u64) < limit;
+        if allowed {
+            hits.push_back(now);
+        }
+
+        let reset_after = hits.front()
+            .map_or(window, |first| window.saturating_sub(now.duration_since(*first)));
+
+        Decision {
+            allowed,
+            limit,
+            remaining: limit.saturating_sub(hits.len() as u64),
+            reset_after,
+        }
+    }
+}
+
+async fn connect(redis_url: &str) -> Result<ConnectionManager> {
+    let client = redis::Client::open(redis_url)?;
+    let connection = tokio::time::timeout(Duration::from_secs(5), ConnectionManager::new(client)).await??;
+    Ok(connection)
+}
+
+/// Limits requests per caller: API key or wallet when authenticated, client
+/// IP otherwise. Adds `X-RateLimit-*` headers to every response.
+pub async fn rate_limit(
+    State((limiter, class)): State<(RateLimiter, RouteClass)>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let ip = || match client_ip(request.headers(), request.extensions()) {
+        Some(ip) => format!("ip:{}", ip),
+        None => "ip:unknown".to_string(),
+    };
+
+    let caller = match request.extensions().get::<AuthUser>() {
+        _ if class == RouteClass::Ip => ip(),
+        Some(AuthUser { api_key_id: Some(id), .. }) => format!("key:{}", id),
+        Some(AuthUser { wallet: Some(wallet), .. }) => format!("wallet:{}", wallet),
+        _ => ip(),
+    };
+
+    let decision = limiter.check(class, &caller).await;
+
+    if !decision.allowed {
+        let mut response = ApiError::RateLimited(decision.reset_after.as_secs().max(1)).into_response();
+        set_headers(response.headers_mut(), &decision);
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+
+    // The per-caller budget below it sets the headers clients go by
+    if class != RouteClass::Ip {
+        set_headers(response.headers_mut(), &decision);
+    }
+    response
+}
+
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert("X-RateLimit-Limit", HeaderValue::from(decision.limit));
+    headers.insert("X-RateLimit-Remaining", HeaderValue::from(decision.remaining));
+    headers.insert("X-RateLimit-Reset", HeaderValue::from(decision.reset_after.as_secs().max(1)));
+}
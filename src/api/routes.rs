@@ -1,55 +1,211 @@
 use axum::{
+    Extension,
     Router,
     routing::{get, post, delete},
     middleware,
 };
 use axum::extract::State;
-use tower_http::cors::CorsLayer;
+use std::time::Duration;
 use tower_http::trace::TraceLayer;
 
+use crate::api::auth::{self, AuthState, Role};
+use crate::api::cors::cors_layer;
+use crate::api::idempotency::{self, IdempotencyState};
+use crate::api::rate_limit::{self, RateLimiter, RouteClass};
 use crate::config::Config;
 use crate::database::DatabasePool;
+use crate::services::api_keys::scopes;
+use crate::services::siws::SiwsService;
 use crate::services::vault::VaultService;
 
 pub fn create_router(
     db_pool: DatabasePool,
     vault_service: VaultService,
+    auth_state: AuthState,
+    siws_service: SiwsService,
+    rate_limiter: RateLimiter,
     config: Config,
 ) -> Router {
-    let api_v1_router = Router::new()
+    // Public reads can be open to any origin while transactions and admin
+    // routes are limited to our own frontends
+    let cors = config.cors_config();
+    let read_cors = cors_layer(&cors.default_origins, cors.allow_credentials);
+    let transaction_cors = cors_layer(&cors.transaction_origins, cors.allow_credentials);
+    let admin_cors = cors_layer(&cors.admin_origins, cors.allow_credentials);
+
+    // Retried state-changing requests replay their first response
+    let idempotent = middleware::from_fn_with_state(
+        IdempotencyState {
+            db_pool: db_pool.clone(),
+            ttl: Duration::from_secs(config.idempotency_key_ttl_secs),
+        },
+        idempotency::idempotency,
+    );
+
+    // Lookups and transaction sending are budgeted separately
+    let read_limit = middleware::from_fn_with_state((rate_limiter.clone(), RouteClass::Read), rate_limit::rate_limit);
+    let send_limit = middleware::from_fn_with_state((rate_limiter.clone(), RouteClass::Send), rate_limit::rate_limit);
+    let ip_limit = middleware::from_fn_with_state((rate_limiter, RouteClass::Ip), rate_limit::rate_limit);
+
+    // Authentication runs inside each group's CORS layer so that 401s, and
+    // 429s from the per-IP budget in front of it, still carry CORS headers
+    let authenticate = middleware::from_fn_with_state(auth_state.clone(), auth::auth_middleware);
+
+    let authenticated_router = Router::new()
         // Health check
         .route("/health", get(handlers::health_check))
         
+        // Caller identity
+        .route("/auth/me", get(handlers::get_current_user))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(read_cors.clone());
+
+    let read_router = Router::new()
+        .route("/vaults/:owner", get(handlers::get_vault))
+        .route("/vaults/:owner/onchain", get(handlers::get_onchain_vault))
+        .route("/vaults/:owner/events", get(handlers::get_vault_events))
+        
+        // Fee estimates
+        .route("/fees", get(handlers::get_fee_estimates))
+        
+        .route("/transactions/:signature", get(handlers::get_transaction_status))
+        
+        // Event stream
+        .route("/events/stream", get(handlers::stream_events))
+        .route_layer(middleware::from_fn_with_state(scopes::VAULT_READ, auth::require_scope))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(read_cors);
+
+    let write_router = Router::new()
         // Vault operations
         .route("/vaults", post(handlers::create_vault))
-        .route("/vaults/:owner", get(handlers::get_vault))
         .route("/vaults/:owner/deposit", post(handlers::deposit))
         .route("/vaults/:owner/withdraw", post(handlers::withdraw))
         .route("/vaults/:owner/close", post(handlers::close_vault))
         
+        // Transaction endpoints
+        .route("/transactions/build/:tx_type", post(handlers::build_transaction))
+        .route("/transactions/submit", post(handlers::submit_transaction))
+        .route_layer(idempotent.clone())
+        .route_layer(middleware::from_fn_with_state(scopes::VAULT_WRITE, auth::require_scope))
+        .route_layer(send_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(transaction_cors.clone());
+
+    let collateral_router = Router::new()
         // Collateral operations
         .route("/vaults/:owner/lock", post(handlers::lock_collateral))
         .route("/vaults/:owner/unlock", post(handlers::unlock_collateral))
         .route("/vaults/:owner/transfer", post(handlers::transfer_collateral))
-        
-        // Admin operations
-        .route("/admin/authority", post(handlers::initialize_authority))
+        .route_layer(idempotent.clone())
+        .route_layer(middleware::from_fn_with_state(scopes::COLLATERAL_LOCK, auth::require_scope))
+        .route_layer(send_limit)
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(transaction_cors.clone());
+
+    // Admin operations, guarded by staff role. Methods on the same path can
+    // need different roles, so each group registers its own handlers.
+    let viewer_router = Router::new()
+        .route("/admin/reconciliation/drift", get(handlers::get_drift_report))
+        .route("/admin/backfill", get(handlers::get_backfill_status))
+        .route("/admin/lookup-table", get(handlers::get_lookup_table))
+        .route("/admin/rpc/endpoints", get(handlers::get_rpc_endpoints))
+        .route("/admin/nonces", get(handlers::list_nonce_accounts))
+        .route("/events", get(handlers::list_all_events))
+        .route_layer(middleware::from_fn_with_state(Role::Viewer, auth::require_role))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(admin_cors.clone());
+
+    let operator_router = Router::new()
+        .route("/admin/backfill", post(handlers::start_backfill))
+        .route("/admin/lookup-table", post(handlers::create_lookup_table))
+        .route("/admin/lookup-table/extend", post(handlers::extend_lookup_table))
+        .route("/admin/nonces", post(handlers::create_nonce_accounts))
+        .route_layer(idempotent.clone())
+        .route_layer(middleware::from_fn_with_state(Role::Operator, auth::require_role))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(admin_cors.clone());
+
+    let risk_admin_router = Router::new()
         .route("/admin/authority/programs", post(handlers::add_authorized_program))
         .route("/admin/authority/programs/:program", delete(handlers::remove_authorized_program))
-        
-        // Transaction endpoints
-        .route("/transactions/build/:tx_type", post(handlers::build_transaction))
-        .route("/transactions/submit", post(handlers::submit_transaction))
-        .route("/transactions/:signature", get(handlers::get_transaction_status))
-        
-        // Event stream
-        .route("/events/stream", get(handlers::stream_events))
-        
-        .layer(middleware::from_fn(handlers::auth_middleware))
-        .with_state((db_pool, vault_service));
+        .route_layer(idempotent.clone())
+        .route_layer(middleware::from_fn_with_state(Role::RiskAdmin, auth::require_role))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(admin_cors.clone());
+
+    let super_admin_router = Router::new()
+        .route("/admin/authority", post(handlers::initialize_authority))
+        .route_layer(idempotent)
+        .route_layer(middleware::from_fn_with_state(Role::SuperAdmin, auth::require_role))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(admin_cors.clone());
+
+    // Partner key management. Not idempotent: replaying would mean storing
+    // the plaintext key alongside the response.
+    let api_key_router = Router::new()
+        .route("/admin/api-keys", get(handlers::list_api_keys).post(handlers::create_api_key))
+        .route("/admin/api-keys/:id", delete(handlers::revoke_api_key))
+        .route("/admin/api-keys/:id/rotate", post(handlers::rotate_api_key))
+        .route_layer(middleware::from_fn_with_state(Role::SuperAdmin, auth::require_role))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate.clone())
+        .route_layer(ip_limit.clone())
+        .layer(admin_cors)
+        .with_state((db_pool.clone(), auth_state.api_keys.clone()));
+
+    // Vault listing for the ops dashboard. It shares `/vaults` with vault
+    // creation, whose router would otherwise answer its preflight, so it is
+    // merged last with a policy that also admits the dashboard's origins.
+    let dashboard_origins: Vec<String> = cors.transaction_origins.iter()
+        .chain(cors.admin_origins.iter())
+        .cloned()
+        .collect();
+    let vault_list_router = Router::new()
+        .route("/vaults", get(handlers::list_vaults))
+        .route_layer(middleware::from_fn_with_state(Role::Viewer, auth::require_role))
+        .route_layer(read_limit.clone())
+        .route_layer(authenticate)
+        .route_layer(ip_limit.clone())
+        .layer(cors_layer(&dashboard_origins, cors.allow_credentials));
+
+    let api_v1_router = authenticated_router
+        .merge(read_router)
+        .merge(write_router)
+        .merge(collateral_router)
+        .merge(viewer_router)
+        .merge(operator_router)
+        .merge(risk_admin_router)
+        .merge(super_admin_router)
+        .merge(vault_list_router)
+        .with_state((db_pool, vault_service))
+        .merge(api_key_router);
+
+    // Wallet sign-in happens before the caller has a token
+    let auth_router = Router::new()
+        .route("/auth/siws/challenge", post(handlers::siws_challenge))
+        .route("/auth/siws/verify", post(handlers::siws_verify))
+        .route_layer(read_limit)
+        .route_layer(ip_limit)
+        .layer(transaction_cors)
+        .with_state(siws_service);
 
     Router::new()
-        .nest("/api/v1", api_v1_router)
-        .layer(CorsLayer::permissive())
+        .nest("/api/v1", auth_router.merge(api_v1_router))
+        .layer(Extension(config.trusted_proxies.clone()))
         .layer(TraceLayer::new_for_http())
-}
\ No newline at end of file
+}
@@ -1,19 +1,54 @@
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::api::auth::TrustedProxies;
+use crate::api::cors::CorsConfig;
+use crate::api::rate_limit::RateLimitConfig;
+use crate::services::sender::SendConfig;
+use crate::services::siws::SiwsConfig;
 
 #[derive(Debug, Clone)]
 pub struct Config {
     pub port: u16,
     pub database_url: String,
     pub redis_url: String,
-    pub rpc_url: String,
+    pub rpc_urls: Vec<String>,
+    pub rpc_health_check_interval_secs: u64,
     pub ws_url: String,
     pub program_id: String,
     pub admin_keypair_path: PathBuf,
+    pub idl_path: PathBuf,
+    pub lookup_table_address: Option<String>,
     pub jwt_secret: String,
+    pub jwt_jwks_path: Option<PathBuf>,
+    pub jwt_issuer: Option<String>,
+    pub siws_domain: String,
+    pub siws_uri: String,
+    pub siws_chain_id: String,
+    pub siws_challenge_ttl_secs: u64,
+    pub session_ttl_secs: u64,
+    pub admin_wallets: Vec<String>,
+    pub api_key_cache_ttl_secs: u64,
+    pub idempotency_key_ttl_secs: u64,
     pub cors_origins: Vec<String>,
+    pub cors_transaction_origins: Vec<String>,
+    pub cors_admin_origins: Vec<String>,
+    pub cors_allow_credentials: bool,
     pub rate_limit_requests: u64,
     pub rate_limit_duration: u64,
+    pub rate_limit_send_requests: u64,
+    pub rate_limit_ip_requests: u64,
+    pub trusted_proxies: TrustedProxies,
+    pub reconcile_interval_secs: u64,
+    pub tx_poll_interval_secs: u64,
+    pub send_rebroadcast_interval_ms: u64,
+    pub send_max_attempts: u32,
+    pub priority_fee_bump_percent: u64,
+    pub min_priority_fee: u64,
+    pub max_priority_fee: u64,
+    pub nonce_reservation_ttl_secs: u64,
+    pub event_stream_buffer: usize,
 }
 
 impl Config {
@@ -30,8 +65,18 @@ impl Config {
         let redis_url = env::var("REDIS_URL")
             .unwrap_or_else(|_| "redis://localhost:6379".to_string());
         
-        let rpc_url = env::var("RPC_URL")
-            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string());
+        // RPC_URLS takes a comma-separated list; RPC_URL is kept for single-endpoint setups
+        let rpc_urls = env::var("RPC_URLS")
+            .or_else(|_| env::var("RPC_URL"))
+            .unwrap_or_else(|_| "https://api.devnet.solana.com".to_string())
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        
+        let rpc_health_check_interval_secs = env::var("RPC_HEALTH_CHECK_INTERVAL_SECS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()?;
         
         let ws_url = env::var("WS_URL")
             .unwrap_or_else(|_| "wss://api.devnet.solana.com".to_string());
@@ -44,15 +89,85 @@ impl Config {
                 .unwrap_or_else(|_| "./admin-keypair.json".to_string())
         );
         
+        let idl_path = PathBuf::from(
+            env::var("IDL_PATH")
+                .unwrap_or_else(|_| "./idl/collateral_vault.json".to_string())
+        );
+        
+        let lookup_table_address = env::var("LOOKUP_TABLE_ADDRESS")
+            .ok()
+            .filter(|s| !s.is_empty());
+        
         let jwt_secret = env::var("JWT_SECRET")
             .expect("JWT_SECRET must be set");
         
-        let cors_origins = env::var("CORS_ORIGINS")
+        let jwt_jwks_path = env::var("JWT_JWKS_PATH")
+            .ok()
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from);
+        
+        let jwt_issuer = env::var("JWT_ISSUER")
+            .ok()
+            .filter(|s| !s.is_empty());
+        
+        let siws_domain = env::var("SIWS_DOMAIN")
+            .unwrap_or_else(|_| format!("localhost:{}", port));
+        
+        let siws_uri = env::var("SIWS_URI")
+            .unwrap_or_else(|_| format!("http://{}", siws_domain));
+        
+        let siws_chain_id = env::var("SIWS_CHAIN_ID")
+            .unwrap_or_else(|_| "devnet".to_string());
+        
+        let siws_challenge_ttl_secs = env::var("SIWS_CHALLENGE_TTL_SECS")
+            .unwrap_or_else(|_| "300".to_string())
+            .parse()?;
+        
+        let session_ttl_secs = env::var("SESSION_TTL_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse()?;
+        
+        let admin_wallets = env::var("ADMIN_WALLETS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        
+        let api_key_cache_ttl_secs = env::var("API_KEY_CACHE_TTL_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()?;
+        
+        let idempotency_key_ttl_secs = env::var("IDEMPOTENCY_KEY_TTL_SECS")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse()?;
+        
+        let cors_origins: Vec<String> = env::var("CORS_ORIGINS")
             .unwrap_or_else(|_| "*".to_string())
             .split(',')
             .map(|s| s.trim().to_string())
             .collect();
         
+        // Route groups without their own list use CORS_ORIGINS
+        let cors_transaction_origins = env::var("CORS_TRANSACTION_ORIGINS")
+            .map(|origins| origins.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+            .unwrap_or_else(|_| cors_origins.clone());
+        
+        let cors_admin_origins = env::var("CORS_ADMIN_ORIGINS")
+            .map(|origins| origins.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+            .unwrap_or_else(|_| cors_origins.clone());
+        
+        let cors_allow_credentials = env::var("CORS_ALLOW_CREDENTIALS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()?;
+        
+        let any_origin = [&cors_origins, &cors_transaction_origins, &cors_admin_origins]
+            .iter()
+            .any(|origins| origins.iter().any(|o| o == "*"));
+        if cors_allow_credentials && any_origin {
+            anyhow::bail!("CORS_ALLOW_CREDENTIALS can't be combined with a `*` origin");
+        }
+        
         let rate_limit_requests = env::var("RATE_LIMIT_REQUESTS")
             .unwrap_or_else(|_| "100".to_string())
             .parse()?;
@@ -61,18 +176,137 @@ impl Config {
             .unwrap_or_else(|_| "3600".to_string())
             .parse()?;
         
+        let rate_limit_send_requests = env::var("RATE_LIMIT_SEND_REQUESTS")
+            .unwrap_or_else(|_| "20".to_string())
+            .parse()?;
+        
+        let rate_limit_ip_requests = env::var("RATE_LIMIT_IP_REQUESTS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()?;
+        
+        let trusted_proxies: Vec<String> = env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let trusted_proxies = TrustedProxies::parse(&trusted_proxies)?;
+        
+        let reconcile_interval_secs = env::var("RECONCILE_INTERVAL_SECS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()?;
+        
+        let tx_poll_interval_secs = env::var("TX_POLL_INTERVAL_SECS")
+            .unwrap_or_else(|_| "2".to_string())
+            .parse()?;
+        
+        let send_rebroadcast_interval_ms = env::var("SEND_REBROADCAST_INTERVAL_MS")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse()?;
+        
+        let send_max_attempts = env::var("SEND_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()?;
+        
+        let priority_fee_bump_percent = env::var("PRIORITY_FEE_BUMP_PERCENT")
+            .unwrap_or_else(|_| "50".to_string())
+            .parse()?;
+        
+        let min_priority_fee = env::var("MIN_PRIORITY_FEE")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()?;
+        
+        let max_priority_fee = env::var("MAX_PRIORITY_FEE")
+            .unwrap_or_else(|_| "1000000".to_string())
+            .parse()?;
+        
+        let nonce_reservation_ttl_secs = env::var("NONCE_RESERVATION_TTL_SECS")
+            .unwrap_or_else(|_| "86400".to_string())
+            .parse()?;
+        
+        let event_stream_buffer = env::var("EVENT_STREAM_BUFFER")
+            .unwrap_or_else(|_| "1024".to_string())
+            .parse()?;
+        
         Ok(Self {
             port,
             database_url,
             redis_url,
-            rpc_url,
+            rpc_urls,
+            rpc_health_check_interval_secs,
             ws_url,
             program_id,
             admin_keypair_path,
+            idl_path,
+            lookup_table_address,
             jwt_secret,
+            jwt_jwks_path,
+            jwt_issuer,
+            siws_domain,
+            siws_uri,
+            siws_chain_id,
+            siws_challenge_ttl_secs,
+            session_ttl_secs,
+            admin_wallets,
+            api_key_cache_ttl_secs,
+            idempotency_key_ttl_secs,
             cors_origins,
+            cors_transaction_origins,
+            cors_admin_origins,
+            cors_allow_credentials,
             rate_limit_requests,
             rate_limit_duration,
+            rate_limit_send_requests,
+            rate_limit_ip_requests,
+            trusted_proxies,
+            reconcile_interval_secs,
+            tx_poll_interval_secs,
+            send_rebroadcast_interval_ms,
+            send_max_attempts,
+            priority_fee_bump_percent,
+            min_priority_fee,
+            max_priority_fee,
+            nonce_reservation_ttl_secs,
+            event_stream_buffer,
         })
     }
+    
+    pub fn cors_config(&self) -> CorsConfig {
+        CorsConfig {
+            default_origins: self.cors_origins.clone(),
+            transaction_origins: self.cors_transaction_origins.clone(),
+            admin_origins: self.cors_admin_origins.clone(),
+            allow_credentials: self.cors_allow_credentials,
+        }
+    }
+    
+    pub fn rate_limit_config(&self) -> RateLimitConfig {
+        RateLimitConfig {
+            window: Duration::from_secs(self.rate_limit_duration),
+            read_requests: self.rate_limit_requests,
+            send_requests: self.rate_limit_send_requests,
+            ip_requests: self.rate_limit_ip_requests,
+        }
+    }
+    
+    pub fn siws_config(&self) -> SiwsConfig {
+        SiwsConfig {
+            domain: self.siws_domain.clone(),
+            uri: self.siws_uri.clone(),
+            chain_id: self.siws_chain_id.clone(),
+            challenge_ttl: Duration::from_secs(self.siws_challenge_ttl_secs),
+            session_ttl: Duration::from_secs(self.session_ttl_secs),
+            admin_wallets: self.admin_wallets.clone(),
+        }
+    }
+    
+    pub fn send_config(&self) -> SendConfig {
+        SendConfig {
+            rebroadcast_interval: Duration::from_millis(self.send_rebroadcast_interval_ms),
+            max_attempts: self.send_max_attempts,
+            fee_bump_percent: self.priority_fee_bump_percent,
+            min_priority_fee: self.min_priority_fee,
+            max_priority_fee: self.max_priority_fee,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,95 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::database::DatabasePool;
+use crate::models::database::ApiKey;
+
+impl DatabasePool {
+    pub async fn insert_api_key(
+        &self,
+        name: &str,
+        key_prefix: &str,
+        key_hash: &str,
+        scopes: &[String],
+        owners: &[String],
+        expires_at: Option<DateTime<Utc>>,
+        rotated_from: Option<Uuid>,
+    ) -> Result<ApiKey> {
+        let key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            INSERT INTO api_keys (name, key_prefix, key_hash, scopes, owners, expires_at, rotated_from)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(name)
+        .bind(key_prefix)
+        .bind(key_hash)
+        .bind(scopes)
+        .bind(owners)
+        .bind(expires_at)
+        .bind(rotated_from)
+        .fetch_one(self.inner())
+        .await
+        .context("Failed to store API key")?;
+        
+        Ok(key)
+    }
+    
+    pub async fn get_api_key(&self, id: Uuid) -> Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys WHERE id = $1")
+            .bind(id)
+            .fetch_optional(self.inner())
+            .await?;
+        
+        Ok(key)
+    }
+    
+    /// Looks up a key that is neither revoked nor expired.
+    pub async fn find_active_api_key(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT * FROM api_keys
+            WHERE key_hash = $1
+              AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(self.inner())
+        .await
+        .context("Failed to look up API key")?;
+        
+        Ok(key)
+    }
+    
+    pub async fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        let keys = sqlx::query_as::<_, ApiKey>("SELECT * FROM api_keys ORDER BY created_at DESC")
+            .fetch_all(self.inner())
+            .await?;
+        
+        Ok(keys)
+    }
+    
+    pub async fn revoke_api_key(&self, id: Uuid) -> Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>(
+            "UPDATE api_keys SET revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1 RETURNING *",
+        )
+        .bind(id)
+        .fetch_optional(self.inner())
+        .await
+        .context("Failed to revoke API key")?;
+        
+        Ok(key)
+    }
+    
+    pub async fn touch_api_key(&self, id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE api_keys SET last_used_at = NOW() WHERE id = $1")
+            .bind(id)
+            .execute(self.inner())
+            .await?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,38 @@
+use anyhow::{Result, Context};
+use std::net::IpAddr;
+
+use crate::database::DatabasePool;
+
+impl DatabasePool {
+    pub async fn insert_audit_entry(
+        &self,
+        action: &str,
+        actor: &str,
+        target: Option<&str>,
+        old_values: Option<&serde_json::Value>,
+        new_values: Option<&serde_json::Value>,
+        ip_address: Option<IpAddr>,
+        user_agent: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_trail (
+                action, actor, target, old_values, new_values, ip_address, user_agent
+            )
+            VALUES ($1, $2, $3, $4, $5, $6::inet, $7)
+            "#,
+        )
+        .bind(action)
+        .bind(actor)
+        .bind(target)
+        .bind(old_values)
+        .bind(new_values)
+        .bind(ip_address.map(|ip| ip.to_string()))
+        .bind(user_agent)
+        .execute(self.inner())
+        .await
+        .context("Failed to write audit entry")?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+use anyhow::{Result, Context};
+use chrono::{DateTime, Utc};
+
+use crate::database::DatabasePool;
+use crate::models::database::SiwsChallenge;
+
+impl DatabasePool {
+    pub async fn insert_siws_challenge(
+        &self,
+        nonce: &str,
+        wallet: &str,
+        message: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO siws_challenges (nonce, wallet, message, expires_at) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(nonce)
+        .bind(wallet)
+        .bind(message)
+        .bind(expires_at)
+        .execute(self.inner())
+        .await
+        .context("Failed to store sign-in challenge")?;
+        
+        Ok(())
+    }
+    
+    /// Marks an unexpired challenge as used and returns it. Returns `None`
+    /// if the nonce is unknown, expired, already used or issued to another wallet.
+    pub async fn consume_siws_challenge(&self, nonce: &str, wallet: &str) -> Result<Option<SiwsChallenge>> {
+        let challenge = sqlx::query_as::<_, SiwsChallenge>(
+            r#"
+            UPDATE siws_challenges
+            SET used_at = NOW()
+            WHERE nonce = $1 AND wallet = $2 AND used_at IS NULL AND expires_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(nonce)
+        .bind(wallet)
+        .fetch_optional(self.inner())
+        .await
+        .context("Failed to consume sign-in challenge")?;
+        
+        Ok(challenge)
+    }
+}
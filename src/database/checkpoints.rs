@@ -0,0 +1,146 @@
+use anyhow::{Result, Context};
+use sqlx::{Connection, PgConnection};
+
+use crate::database::DatabasePool;
+use crate::models::database::BackfillCheckpoint;
+
+/// Advisory lock key taken for the length of a backfill, by the API and the
+/// `backfill` subcommand alike.
+const BACKFILL_LOCK_KEY: i64 = 0x6261_636b;
+
+/// A held backfill lock. It's a session lock on a connection of its own, so
+/// it also goes away if the process dies mid-run.
+pub struct BackfillLock {
+    conn: PgConnection,
+}
+
+impl BackfillLock {
+    pub async fn release(mut self) {
+        let unlocked = sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(BACKFILL_LOCK_KEY)
+            .execute(&mut self.conn)
+            .await;
+
+        if let Err(e) = unlocked {
+            tracing::warn!("Failed to release backfill lock, closing its connection: {:?}", e);
+        }
+
+        let _ = self.conn.close().await;
+    }
+}
+
+impl DatabasePool {
+    /// Takes the backfill lock, or returns `None` if another backfill holds it.
+    pub async fn try_lock_backfill(&self) -> Result<Option<BackfillLock>> {
+        let mut conn = self.inner().acquire().await?.detach();
+        
+        let locked: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1)")
+            .bind(BACKFILL_LOCK_KEY)
+            .fetch_one(&mut conn)
+            .await
+            .context("Failed to take backfill lock")?;
+        
+        Ok(locked.then_some(BackfillLock { conn }))
+    }
+    
+    pub async fn is_backfill_running(&self) -> Result<bool> {
+        // A bigint advisory key shows up split into classid and objid
+        let running: bool = sqlx::query_scalar(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM pg_locks
+                WHERE locktype = 'advisory'
+                  AND database = (SELECT oid FROM pg_database WHERE datname = current_database())
+                  AND classid = ($1 >> 32)::oid
+                  AND objid = ($1 & 4294967295)::oid
+                  AND objsubid = 1
+                  AND granted
+            )
+            "#,
+        )
+        .bind(BACKFILL_LOCK_KEY)
+        .fetch_one(self.inner())
+        .await?;
+        
+        Ok(running)
+    }
+    
+
+    pub async fn get_backfill_checkpoint(&self, address: &str) -> Result<Option<BackfillCheckpoint>> {
+        let checkpoint = sqlx::query_as::<_, BackfillCheckpoint>(
+            "SELECT * FROM backfill_checkpoints WHERE address = $1",
+        )
+        .bind(address)
+        .fetch_optional(self.inner())
+        .await?;
+        
+        Ok(checkpoint)
+    }
+    
+    pub async fn list_backfill_checkpoints(&self) -> Result<Vec<BackfillCheckpoint>> {
+        let checkpoints = sqlx::query_as::<_, BackfillCheckpoint>(
+            "SELECT * FROM backfill_checkpoints ORDER BY address",
+        )
+        .fetch_all(self.inner())
+        .await?;
+        
+        Ok(checkpoints)
+    }
+    
+    /// Records progress after a page of signatures has been processed.
+    /// `newest_signature` is only set the first time so the top of the
+    /// walk is remembered across resumes.
+    pub async fn save_backfill_checkpoint(
+        &self,
+        address: &str,
+        newest_signature: Option<&str>,
+        oldest_signature: Option<&str>,
+        processed: u64,
+        completed: bool,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO backfill_checkpoints (
+                address, newest_signature, oldest_signature, completed, signatures_processed
+            )
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (address) DO UPDATE
+            SET newest_signature = COALESCE(backfill_checkpoints.newest_signature, EXCLUDED.newest_signature),
+                oldest_signature = COALESCE(EXCLUDED.oldest_signature, backfill_checkpoints.oldest_signature),
+                completed = EXCLUDED.completed,
+                signatures_processed = backfill_checkpoints.signatures_processed + EXCLUDED.signatures_processed
+            "#,
+        )
+        .bind(address)
+        .bind(newest_signature)
+        .bind(oldest_signature)
+        .bind(completed)
+        .bind(processed as i64)
+        .execute(self.inner())
+        .await
+        .context("Failed to save backfill checkpoint")?;
+        
+        Ok(())
+    }
+    
+    /// Moves the top of a completed walk forward after catching up on
+    /// signatures newer than the previous `newest_signature`.
+    pub async fn advance_backfill_head(&self, address: &str, newest_signature: &str, processed: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE backfill_checkpoints
+            SET newest_signature = $2,
+                signatures_processed = signatures_processed + $3
+            WHERE address = $1
+            "#,
+        )
+        .bind(address)
+        .bind(newest_signature)
+        .bind(processed as i64)
+        .execute(self.inner())
+        .await
+        .context("Failed to advance backfill checkpoint")?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{Result, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::database::DatabasePool;
+use crate::models::database::VaultEvent;
+
+/// Filters for `list_vault_events`; unset fields don't filter.
+#[derive(Debug, Clone, Default)]
+pub struct VaultEventFilter {
+    pub owner: Option<String>,
+    /// Matches any of these types; empty matches all.
+    pub event_types: Vec<String>,
+    pub after: Option<DateTime<Utc>>,
+    pub before: Option<DateTime<Utc>>,
+    pub signature: Option<String>,
+    /// Program that locked, unlocked or transferred the collateral.
+    pub caller_program: Option<String>,
+}
+
+impl VaultEventFilter {
+    /// Same checks as the SQL filter, for events that didn't come from a query.
+    pub fn matches(&self, event: &VaultEvent) -> bool {
+        let data_field = |field: &str| event.data.get(field).and_then(|v| v.as_str());
+        
+        self.owner.as_ref().map_or(true, |owner| &event.vault_owner == owner)
+            && (self.event_types.is_empty() || self.event_types.contains(&event.event_type))
+            && self.after.map_or(true, |after| event.created_at >= after)
+            && self.before.map_or(true, |before| event.created_at < before)
+            && self.signature.as_deref().map_or(true, |s| data_field("signature") == Some(s))
+            && self.caller_program.as_deref().map_or(true, |p| data_field("caller_program") == Some(p))
+    }
+}
+
+/// The last event of a page. Events are listed by `(created_at, id)`
+/// descending, so the next page starts strictly below it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventCursor {
+    pub created_at: DateTime<Utc>,
+    pub id: Uuid,
+}
+
+impl EventCursor {
+    pub fn after(event: &VaultEvent) -> Self {
+        Self {
+            created_at: event.created_at,
+            id: event.id,
+        }
+    }
+    
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+    
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).context("Cursor is not valid base64")?;
+        serde_json::from_slice(&bytes).context("Cursor is malformed")
+    }
+}
+
+fn push_event_filters(builder: &mut QueryBuilder<'_, Postgres>, filter: &VaultEventFilter) {
+    builder.push(" WHERE TRUE");
+    
+    if let Some(owner) = &filter.owner {
+        builder.push(" AND vault_owner = ").push_bind(owner.clone());
+    }
+    if !filter.event_types.is_empty() {
+        builder.push(" AND event_type = ANY(").push_bind(filter.event_types.clone()).push(")");
+    }
+    if let Some(after) = filter.after {
+        builder.push(" AND created_at >= ").push_bind(after);
+    }
+    if let Some(before) = filter.before {
+        builder.push(" AND created_at < ").push_bind(before);
+    }
+    if let Some(signature) = &filter.signature {
+        builder.push(" AND data->>'signature' = ").push_bind(signature.clone());
+    }
+    if let Some(caller_program) = &filter.caller_program {
+        builder.push(" AND data->>'caller_program' = ").push_bind(caller_program.clone());
+    }
+}
+
+impl DatabasePool {
+    pub async fn store_vault_event(
+        &self,
+        vault_owner: &str,
+        event_type: &str,
+        data: &serde_json::Value,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO vault_events (id, vault_owner, event_type, data)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(vault_owner)
+        .bind(event_type)
+        .bind(data)
+        .execute(self.inner())
+        .await
+        .context("Failed to store vault event")?;
+        
+        Ok(())
+    }
+    
+    /// Records an event observed on chain. Skips it when this signature has
+    /// already been logged for the owner (e.g. by `VaultService` or a
+    /// previous indexer run) or when the vault is not known yet. Returns
+    /// whether a row was inserted.
+    pub async fn store_indexed_vault_event(
+        &self,
+        owner: &str,
+        event_type: &str,
+        signature: &str,
+        slot: u64,
+        block_time: Option<i64>,
+        data: &serde_json::Value,
+    ) -> Result<bool> {
+        let mut data = data.clone();
+        data["signature"] = serde_json::json!(signature);
+        data["slot"] = serde_json::json!(slot);
+        
+        let result = sqlx::query(
+            r#"
+            INSERT INTO vault_events (id, vault_owner, event_type, data, created_at)
+            SELECT $1, $2, $3, $4, COALESCE(to_timestamp($6), CURRENT_TIMESTAMP)
+            WHERE EXISTS (SELECT 1 FROM vaults WHERE owner = $2)
+              AND NOT EXISTS (
+                  SELECT 1 FROM vault_events
+                  WHERE vault_owner = $2 AND event_type = $3 AND data->>'signature' = $5
+              )
+            "#,
+        )
+        .bind(uuid::Uuid::new_v4())
+        .bind(owner)
+        .bind(event_type)
+        .bind(&data)
+        .bind(signature)
+        .bind(block_time.map(|t| t as f64))
+        .execute(self.inner())
+        .await
+        .context("Failed to store indexed vault event")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// A page of events matching `filter`, newest first.
+    pub async fn list_vault_events(
+        &self,
+        filter: &VaultEventFilter,
+        cursor: Option<&EventCursor>,
+        limit: i64,
+    ) -> Result<Vec<VaultEvent>> {
+        let mut builder = QueryBuilder::new("SELECT * FROM vault_events");
+        push_event_filters(&mut builder, filter);
+        
+        if let Some(cursor) = cursor {
+            builder
+                .push(" AND (created_at, id) < (")
+                .push_bind(cursor.created_at)
+                .push(", ")
+                .push_bind(cursor.id)
+                .push(")");
+        }
+        
+        builder.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(limit);
+        
+        let events = builder
+            .build_query_as::<VaultEvent>()
+            .fetch_all(self.inner())
+            .await
+            .context("Failed to list vault events")?;
+        
+        Ok(events)
+    }
+    
+    pub async fn get_vault_event(&self, id: Uuid) -> Result<Option<VaultEvent>> {
+        let event = sqlx::query_as::<_, VaultEvent>(
+            "SELECT * FROM vault_events WHERE id = $1",
+        )
+        .bind(id)
+        .fetch_optional(self.inner())
+        .await
+        .context("Failed to fetch vault event")?;
+        
+        Ok(event)
+    }
+    
+    /// Events matching `filter` inserted after `after_seq`, in insertion
+    /// order. Used to catch a stream subscriber up on what it missed.
+    pub async fn list_vault_events_since(
+        &self,
+        filter: &VaultEventFilter,
+        after_seq: i64,
+        limit: i64,
+    ) -> Result<Vec<VaultEvent>> {
+        let mut builder = QueryBuilder::new("SELECT * FROM vault_events");
+        push_event_filters(&mut builder, filter);
+        
+        builder.push(" AND seq > ").push_bind(after_seq);
+        builder.push(" ORDER BY seq ASC LIMIT ").push_bind(limit);
+        
+        let events = builder
+            .build_query_as::<VaultEvent>()
+            .fetch_all(self.inner())
+            .await
+            .context("Failed to list vault events")?;
+        
+        Ok(events)
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Result, Context};
+
+use crate::database::DatabasePool;
+use crate::models::database::IdempotencyKey;
+
+impl DatabasePool {
+    /// Claims `key` for a new request, leased for `lease_secs`. Rows older
+    /// than `ttl_secs`, and unfinished rows whose lease ran out, are
+    /// reclaimed. Returns `false` if the key is already held.
+    pub async fn claim_idempotency_key(
+        &self,
+        caller: &str,
+        key: &str,
+        method: &str,
+        path: &str,
+        request_hash: &str,
+        ttl_secs: i64,
+        lease_secs: i64,
+    ) -> Result<bool> {
+        let claimed = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (caller, key, method, path, request_hash, locked_until)
+            VALUES ($1, $2, $3, $4, $5, NOW() + make_interval(secs => $7))
+            ON CONFLICT (caller, key) DO UPDATE
+            SET method = EXCLUDED.method,
+                path = EXCLUDED.path,
+                request_hash = EXCLUDED.request_hash,
+                status_code = NULL,
+                response_body = NULL,
+                completed_at = NULL,
+                locked_until = EXCLUDED.locked_until,
+                created_at = NOW()
+            WHERE idempotency_keys.created_at < NOW() - make_interval(secs => $6)
+               OR (idempotency_keys.completed_at IS NULL AND idempotency_keys.locked_until < NOW())
+            RETURNING key
+            "#,
+        )
+        .bind(caller)
+        .bind(key)
+        .bind(method)
+        .bind(path)
+        .bind(request_hash)
+        .bind(ttl_secs as f64)
+        .bind(lease_secs as f64)
+        .fetch_optional(self.inner())
+        .await
+        .context("Failed to claim idempotency key")?;
+        
+        Ok(claimed.is_some())
+    }
+    
+    /// Keeps a claim alive while its request is still being processed.
+    pub async fn extend_idempotency_lease(&self, caller: &str, key: &str, lease_secs: i64) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET locked_until = NOW() + make_interval(secs => $3)
+            WHERE caller = $1 AND key = $2 AND completed_at IS NULL
+            "#,
+        )
+        .bind(caller)
+        .bind(key)
+        .bind(lease_secs as f64)
+        .execute(self.inner())
+        .await
+        .context("Failed to extend idempotency key lease")?;
+        
+        Ok(())
+    }
+    
+    /// Gives up an unfinished claim so the key can be used again.
+    pub async fn release_idempotency_key(&self, caller: &str, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE caller = $1 AND key = $2 AND completed_at IS NULL")
+            .bind(caller)
+            .bind(key)
+            .execute(self.inner())
+            .await
+            .context("Failed to release idempotency key")?;
+        
+        Ok(())
+    }
+    
+    /// Deletes keys older than `ttl_secs` that are no longer held.
+    pub async fn delete_expired_idempotency_keys(&self, ttl_secs: i64) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE created_at < NOW() - make_interval(secs => $1)
+              AND (completed_at IS NOT NULL OR locked_until IS NULL OR locked_until < NOW())
+            "#,
+        )
+        .bind(ttl_secs as f64)
+        .execute(self.inner())
+        .await
+        .context("Failed to delete expired idempotency keys")?;
+        
+        Ok(result.rows_affected())
+    }
+    
+    pub async fn get_idempotency_key(&self, caller: &str, key: &str) -> Result<Option<IdempotencyKey>> {
+        let row = sqlx::query_as::<_, IdempotencyKey>(
+            "SELECT * FROM idempotency_keys WHERE caller = $1 AND key = $2",
+        )
+        .bind(caller)
+        .bind(key)
+        .fetch_optional(self.inner())
+        .await?;
+        
+        Ok(row)
+    }
+    
+    pub async fn complete_idempotency_key(
+        &self,
+        caller: &str,
+        key: &str,
+        status_code: u16,
+        response_body: &[u8],
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET status_code = $3, response_body = $4, completed_at = NOW()
+            WHERE caller = $1 AND key = $2
+            "#,
+        )
+        .bind(caller)
+        .bind(key)
+        .bind(status_code as i32)
+        .bind(response_body)
+        .execute(self.inner())
+        .await
+        .context("Failed to store idempotent response")?;
+        
+        Ok(())
+    }
+}
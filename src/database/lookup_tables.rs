@@ -0,0 +1,27 @@
+use anyhow::{Result, Context};
+
+use crate::database::DatabasePool;
+
+impl DatabasePool {
+    pub async fn insert_lookup_table(&self, address: &str, authority: &str) -> Result<()> {
+        sqlx::query("INSERT INTO lookup_tables (address, authority) VALUES ($1, $2)")
+            .bind(address)
+            .bind(authority)
+            .execute(self.inner())
+            .await
+            .context("Failed to store lookup table")?;
+        
+        Ok(())
+    }
+    
+    /// Address of the most recently created lookup table, if any.
+    pub async fn latest_lookup_table(&self) -> Result<Option<String>> {
+        let address = sqlx::query_scalar::<_, String>(
+            "SELECT address FROM lookup_tables ORDER BY created_at DESC LIMIT 1",
+        )
+        .fetch_optional(self.inner())
+        .await?;
+        
+        Ok(address)
+    }
+}
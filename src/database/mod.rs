@@ -2,9 +2,35 @@ use sqlx::{Pool, Postgres, postgres::PgPoolOptions};
 use anyhow::{Result, Context};
 use std::time::Duration;
 
-pub type DatabasePool = Pool<Postgres>;
+pub mod api_keys;
+pub mod audit;
+pub mod challenges;
+pub mod checkpoints;
+pub mod events;
+pub mod idempotency;
+pub mod lookup_tables;
+pub mod nonces;
+pub mod transactions;
+pub mod vaults;
 
-pub async fn create_pool(database_url: &str) -> Result<DatabasePool> {
+#[derive(Clone)]
+pub struct DatabasePool {
+    pool: Pool<Postgres>,
+}
+
+impl DatabasePool {
+    pub async fn new(database_url: &str) -> Result<Self> {
+        Ok(Self {
+            pool: create_pool(database_url).await?,
+        })
+    }
+    
+    pub fn inner(&self) -> &Pool<Postgres> {
+        &self.pool
+    }
+}
+
+pub async fn create_pool(database_url: &str) -> Result<Pool<Postgres>> {
     let pool = PgPoolOptions::new()
         .max_connections(20)
         .acquire_timeout(Duration::from_secs(30))
@@ -13,4 +39,4 @@ pub async fn create_pool(database_url: &str) -> Result<DatabasePool> {
         .context("Failed to create database pool")?;
     
     Ok(pool)
-}
\ No newline at end of file
+}
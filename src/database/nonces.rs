@@ -0,0 +1,95 @@
+use anyhow::{Result, Context};
+
+use crate::database::DatabasePool;
+use crate::models::database::NonceAccount;
+
+impl DatabasePool {
+    pub async fn insert_nonce_account(&self, address: &str, authority: &str) -> Result<()> {
+        sqlx::query("INSERT INTO nonce_accounts (address, authority) VALUES ($1, $2)")
+            .bind(address)
+            .bind(authority)
+            .execute(self.inner())
+            .await
+            .context("Failed to store nonce account")?;
+        
+        Ok(())
+    }
+    
+    pub async fn list_nonce_accounts(&self) -> Result<Vec<NonceAccount>> {
+        let accounts = sqlx::query_as::<_, NonceAccount>(
+            "SELECT * FROM nonce_accounts ORDER BY created_at",
+        )
+        .fetch_all(self.inner())
+        .await?;
+        
+        Ok(accounts)
+    }
+    
+    /// Reserves the least recently used free nonce account. `SKIP LOCKED`
+    /// lets concurrent builds each get a different account.
+    pub async fn reserve_nonce_account(&self) -> Result<Option<NonceAccount>> {
+        let account = sqlx::query_as::<_, NonceAccount>(
+            r#"
+            UPDATE nonce_accounts
+            SET reserved_at = NOW(), signature = NULL
+            WHERE address = (
+                SELECT address FROM nonce_accounts
+                WHERE reserved_at IS NULL
+                ORDER BY updated_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING *
+            "#,
+        )
+        .fetch_optional(self.inner())
+        .await
+        .context("Failed to reserve nonce account")?;
+        
+        Ok(account)
+    }
+    
+    /// Links a reservation to the transaction that was submitted with it.
+    pub async fn attach_nonce_signature(&self, address: &str, signature: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE nonce_accounts SET signature = $2 WHERE address = $1 AND reserved_at IS NOT NULL",
+        )
+        .bind(address)
+        .bind(signature)
+        .execute(self.inner())
+        .await
+        .context("Failed to attach signature to nonce account")?;
+        
+        Ok(())
+    }
+    
+    pub async fn release_nonce_account(&self, address: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE nonce_accounts SET reserved_at = NULL, signature = NULL WHERE address = $1",
+        )
+        .bind(address)
+        .execute(self.inner())
+        .await
+        .context("Failed to release nonce account")?;
+        
+        Ok(())
+    }
+    
+    /// Reservations older than `max_age_secs` that never got a submitted
+    /// transaction attached.
+    pub async fn list_stale_nonce_reservations(&self, max_age_secs: i64) -> Result<Vec<NonceAccount>> {
+        let accounts = sqlx::query_as::<_, NonceAccount>(
+            r#"
+            SELECT * FROM nonce_accounts
+            WHERE reserved_at < NOW() - make_interval(secs => $1)
+              AND signature IS NULL
+            ORDER BY reserved_at
+            "#,
+        )
+        .bind(max_age_secs as f64)
+        .fetch_all(self.inner())
+        .await?;
+        
+        Ok(accounts)
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{Result, Context};
+
+use crate::database::DatabasePool;
+use crate::models::database::TransactionLog;
+
+impl DatabasePool {
+    /// Inserts a transaction log row, or refreshes status and slot if the
+    /// signature is already tracked. The status only moves forward, see
+    /// `transaction_status_rank`; a settled row is never rewritten.
+    pub async fn upsert_transaction_log(
+        &self,
+        signature: &str,
+        vault_owner: Option<&str>,
+        transaction_type: &str,
+        status: &str,
+        slot: Option<u64>,
+        block_time: Option<i64>,
+        fee: Option<u64>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO transaction_logs (
+                signature, vault_owner, transaction_type, status, slot,
+                block_time, fee, error_message
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (signature) DO UPDATE
+            SET status = EXCLUDED.status,
+                slot = COALESCE(EXCLUDED.slot, transaction_logs.slot),
+                block_time = COALESCE(EXCLUDED.block_time, transaction_logs.block_time),
+                fee = COALESCE(EXCLUDED.fee, transaction_logs.fee),
+                error_message = COALESCE(EXCLUDED.error_message, transaction_logs.error_message),
+                vault_owner = COALESCE(transaction_logs.vault_owner, EXCLUDED.vault_owner)
+            WHERE EXCLUDED.status = transaction_logs.status
+               OR transaction_status_rank(EXCLUDED.status) > transaction_status_rank(transaction_logs.status)
+            "#,
+        )
+        .bind(signature)
+        .bind(vault_owner)
+        .bind(transaction_type)
+        .bind(status)
+        .bind(slot.map(|s| s as i64))
+        .bind(block_time)
+        .bind(fee.map(|f| f as i64))
+        .bind(error_message)
+        .execute(self.inner())
+        .await
+        .context("Failed to upsert transaction log")?;
+        
+        Ok(())
+    }
+    
+    pub async fn insert_pending_transaction(
+        &self,
+        signature: &str,
+        vault_owner: Option<&str>,
+        transaction_type: &str,
+        recent_blockhash: &str,
+        nonce_account: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO transaction_logs (
+                signature, vault_owner, transaction_type, status, recent_blockhash, nonce_account
+            )
+            VALUES ($1, $2, $3, 'pending', $4, $5)
+            ON CONFLICT (signature) DO NOTHING
+            "#,
+        )
+        .bind(signature)
+        .bind(vault_owner)
+        .bind(transaction_type)
+        .bind(recent_blockhash)
+        .bind(nonce_account)
+        .execute(self.inner())
+        .await
+        .context("Failed to record pending transaction")?;
+        
+        Ok(())
+    }
+    
+    pub async fn get_transaction_log(&self, signature: &str) -> Result<Option<TransactionLog>> {
+        let log = sqlx::query_as::<_, TransactionLog>(
+            "SELECT * FROM transaction_logs WHERE signature = $1",
+        )
+        .bind(signature)
+        .fetch_optional(self.inner())
+        .await?;
+        
+        Ok(log)
+    }
+    
+    /// Transactions that have not reached `finalized`, `failed` or `expired`.
+    pub async fn list_unsettled_transactions(&self, limit: i64) -> Result<Vec<TransactionLog>> {
+        let logs = sqlx::query_as::<_, TransactionLog>(
+            r#"
+            SELECT * FROM transaction_logs
+            WHERE status IN ('pending', 'processed', 'confirmed')
+            ORDER BY updated_at
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.inner())
+        .await?;
+        
+        Ok(logs)
+    }
+    
+    /// Moves rows to the back of the unsettled queue after a poll that left
+    /// them unchanged.
+    pub async fn touch_transaction_logs(&self, signatures: &[String]) -> Result<()> {
+        sqlx::query("UPDATE transaction_logs SET updated_at = NOW() WHERE signature = ANY($1)")
+            .bind(signatures)
+            .execute(self.inner())
+            .await
+            .context("Failed to touch transaction logs")?;
+        
+        Ok(())
+    }
+    
+    /// Moves a tracked transaction to `status`, under the same forward-only
+    /// rule as `upsert_transaction_log`.
+    pub async fn update_transaction_status(
+        &self,
+        signature: &str,
+        status: &str,
+        slot: Option<u64>,
+        block_time: Option<i64>,
+        fee: Option<u64>,
+        error_message: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE transaction_logs
+            SET status = $2,
+                slot = COALESCE($3, slot),
+                block_time = COALESCE($4, block_time),
+                fee = COALESCE($5, fee),
+                error_message = COALESCE($6, error_message)
+            WHERE signature = $1
+              AND (status = $2 OR transaction_status_rank($2) > transaction_status_rank(status))
+            "#,
+        )
+        .bind(signature)
+        .bind(status)
+        .bind(slot.map(|s| s as i64))
+        .bind(block_time)
+        .bind(fee.map(|f| f as i64))
+        .bind(error_message)
+        .execute(self.inner())
+        .await
+        .context("Failed to update transaction status")?;
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,349 @@
+use anyhow::{Result, Context, bail};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::database::DatabasePool;
+use crate::models::database::Vault;
+use crate::models::requests::{SortOrder, VaultSortField};
+
+/// Mutable view of a vault's balances used by `update_vault_balances`.
+#[derive(Debug, Clone, Copy)]
+pub struct VaultBalances {
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+}
+
+/// No vault is stored for the owner.
+#[derive(Debug, thiserror::Error)]
+#[error("Vault not found for owner {0}")]
+pub struct VaultNotFound(pub String);
+
+/// Filters for `list_vaults_page`; unset fields don't filter.
+#[derive(Debug, Clone, Default)]
+pub struct VaultFilter {
+    pub token_mint: Option<String>,
+    pub min_total_balance: Option<i64>,
+    pub max_total_balance: Option<i64>,
+    pub min_locked_balance: Option<i64>,
+    pub max_locked_balance: Option<i64>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+}
+
+/// Position of the last vault on a page, for keyset pagination. `value` is
+/// the sort column of that vault; `id` breaks ties. A cursor only continues
+/// the `sort_by` and `order` it was issued for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultCursor {
+    pub sort_by: VaultSortField,
+    pub order: SortOrder,
+    pub value: String,
+    pub id: Uuid,
+}
+
+impl VaultCursor {
+    pub fn after(vault: &Vault, sort_by: VaultSortField, order: SortOrder) -> Self {
+        let value = match sort_by {
+            VaultSortField::CreatedAt => vault.created_at.to_rfc3339(),
+            VaultSortField::TotalBalance => vault.total_balance.to_string(),
+            VaultSortField::LockedBalance => vault.locked_balance.to_string(),
+        };
+        
+        Self {
+            sort_by,
+            order,
+            value,
+            id: vault.id,
+        }
+    }
+    
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+    
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).context("Cursor is not valid base64")?;
+        let cursor: Self = serde_json::from_slice(&bytes).context("Cursor is malformed")?;
+        
+        // Reject values the keyset comparison couldn't bind
+        match cursor.sort_by {
+            VaultSortField::CreatedAt => {
+                DateTime::parse_from_rfc3339(&cursor.value).context("Cursor is malformed")?;
+            }
+            VaultSortField::TotalBalance | VaultSortField::LockedBalance => {
+                cursor.value.parse::<i64>().context("Cursor is malformed")?;
+            }
+        }
+        
+        Ok(cursor)
+    }
+}
+
+/// Where a page of `list_vaults_page` starts.
+#[derive(Debug, Clone)]
+pub enum VaultPageStart {
+    Offset(i64),
+    After(VaultCursor),
+}
+
+fn sort_column(sort_by: VaultSortField) -> &'static str {
+    match sort_by {
+        VaultSortField::CreatedAt => "created_at",
+        VaultSortField::TotalBalance => "total_balance",
+        VaultSortField::LockedBalance => "locked_balance",
+    }
+}
+
+fn push_vault_filters(builder: &mut QueryBuilder<'_, Postgres>, filter: &VaultFilter) {
+    builder.push(" WHERE TRUE");
+    
+    if let Some(token_mint) = &filter.token_mint {
+        builder.push(" AND token_mint = ").push_bind(token_mint.clone());
+    }
+    if let Some(min) = filter.min_total_balance {
+        builder.push(" AND total_balance >= ").push_bind(min);
+    }
+    if let Some(max) = filter.max_total_balance {
+        builder.push(" AND total_balance <= ").push_bind(max);
+    }
+    if let Some(min) = filter.min_locked_balance {
+        builder.push(" AND locked_balance >= ").push_bind(min);
+    }
+    if let Some(max) = filter.max_locked_balance {
+        builder.push(" AND locked_balance <= ").push_bind(max);
+    }
+    if let Some(after) = filter.created_after {
+        builder.push(" AND created_at >= ").push_bind(after);
+    }
+    if let Some(before) = filter.created_before {
+        builder.push(" AND created_at < ").push_bind(before);
+    }
+}
+
+impl DatabasePool {
+    pub async fn store_vault(&self, vault: Vault) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO vaults (
+                id, owner, vault_address, token_mint,
+                total_balance, locked_balance, available_balance,
+                total_deposited, total_withdrawn, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(vault.id)
+        .bind(&vault.owner)
+        .bind(&vault.vault_address)
+        .bind(&vault.token_mint)
+        .bind(vault.total_balance)
+        .bind(vault.locked_balance)
+        .bind(vault.available_balance)
+        .bind(vault.total_deposited)
+        .bind(vault.total_withdrawn)
+        .bind(vault.created_at)
+        .bind(vault.updated_at)
+        .execute(self.inner())
+        .await
+        .context("Failed to store vault")?;
+        
+        Ok(())
+    }
+    
+    /// Inserts a vault discovered on chain unless it is already tracked.
+    /// Returns whether a row was inserted.
+    pub async fn store_vault_if_missing(&self, vault: Vault) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO vaults (
+                id, owner, vault_address, token_mint,
+                total_balance, locked_balance, available_balance,
+                total_deposited, total_withdrawn, created_at, updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (owner) DO NOTHING
+            "#,
+        )
+        .bind(vault.id)
+        .bind(&vault.owner)
+        .bind(&vault.vault_address)
+        .bind(&vault.token_mint)
+        .bind(vault.total_balance)
+        .bind(vault.locked_balance)
+        .bind(vault.available_balance)
+        .bind(vault.total_deposited)
+        .bind(vault.total_withdrawn)
+        .bind(vault.created_at)
+        .bind(vault.updated_at)
+        .execute(self.inner())
+        .await
+        .context("Failed to store discovered vault")?;
+        
+        Ok(result.rows_affected() > 0)
+    }
+    
+    pub async fn get_vault(&self, owner: &str) -> Result<Vault> {
+        sqlx::query_as::<_, Vault>("SELECT * FROM vaults WHERE owner = $1")
+            .bind(owner)
+            .fetch_optional(self.inner())
+            .await?
+            .ok_or_else(|| VaultNotFound(owner.to_string()).into())
+    }
+    
+    pub async fn list_vaults(&self) -> Result<Vec<Vault>> {
+        let vaults = sqlx::query_as::<_, Vault>("SELECT * FROM vaults ORDER BY created_at")
+            .fetch_all(self.inner())
+            .await?;
+        
+        Ok(vaults)
+    }
+    
+    /// One page of vaults matching `filter`, ordered by `sort_by` with the
+    /// id as tiebreaker so keyset pages never skip or repeat rows.
+    pub async fn list_vaults_page(
+        &self,
+        filter: &VaultFilter,
+        sort_by: VaultSortField,
+        order: SortOrder,
+        start: &VaultPageStart,
+        limit: i64,
+    ) -> Result<Vec<Vault>> {
+        let column = sort_column(sort_by);
+        let (direction, comparison) = match order {
+            SortOrder::Asc => ("ASC", ">"),
+            SortOrder::Desc => ("DESC", "<"),
+        };
+        
+        let mut builder = QueryBuilder::new("SELECT * FROM vaults");
+        push_vault_filters(&mut builder, filter);
+        
+        if let VaultPageStart::After(cursor) = start {
+            if cursor.sort_by != sort_by || cursor.order != order {
+                bail!("Cursor was issued for a different sort order");
+            }
+            
+            builder.push(format!(" AND ({}, id) {} (", column, comparison));
+            match sort_by {
+                VaultSortField::CreatedAt => {
+                    builder.push_bind(DateTime::parse_from_rfc3339(&cursor.value)?.with_timezone(&Utc));
+                }
+                VaultSortField::TotalBalance | VaultSortField::LockedBalance => {
+                    builder.push_bind(cursor.value.parse::<i64>()?);
+                }
+            }
+            builder.push(", ").push_bind(cursor.id).push(")");
+        }
+        
+        builder.push(format!(" ORDER BY {} {}, id {}", column, direction, direction));
+        builder.push(" LIMIT ").push_bind(limit);
+        
+        if let VaultPageStart::Offset(offset) = start {
+            builder.push(" OFFSET ").push_bind(*offset);
+        }
+        
+        let vaults = builder
+            .build_query_as::<Vault>()
+            .fetch_all(self.inner())
+            .await
+            .context("Failed to list vaults")?;
+        
+        Ok(vaults)
+    }
+    
+    pub async fn count_vaults(&self, filter: &VaultFilter) -> Result<i64> {
+        let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM vaults");
+        push_vault_filters(&mut builder, filter);
+        
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(self.inner())
+            .await
+            .context("Failed to count vaults")?;
+        
+        Ok(count)
+    }
+    
+    /// Token mints held by the most vaults, most common first.
+    pub async fn list_common_mints(&self, limit: i64) -> Result<Vec<String>> {
+        let mints = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT token_mint FROM vaults
+            GROUP BY token_mint
+            ORDER BY COUNT(*) DESC, token_mint
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(self.inner())
+        .await?;
+        
+        Ok(mints)
+    }
+    
+    /// Applies `update` to the vault's balances under a row lock.
+    pub async fn update_vault_balances<F>(&self, owner: &str, update: F) -> Result<()>
+    where
+        F: FnOnce(&mut VaultBalances) -> Result<()>,
+    {
+        let mut tx = self.inner().begin().await?;
+        
+        let vault = sqlx::query_as::<_, Vault>("SELECT * FROM vaults WHERE owner = $1 FOR UPDATE")
+            .bind(owner)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| VaultNotFound(owner.to_string()))?;
+        
+        let mut balances = VaultBalances {
+            total_balance: vault.total_balance as u64,
+            locked_balance: vault.locked_balance as u64,
+            available_balance: vault.available_balance as u64,
+        };
+        
+        update(&mut balances)?;
+        
+        sqlx::query(
+            r#"
+            UPDATE vaults
+            SET total_balance = $2, locked_balance = $3, available_balance = $4
+            WHERE owner = $1
+            "#,
+        )
+        .bind(owner)
+        .bind(balances.total_balance as i64)
+        .bind(balances.locked_balance as i64)
+        .bind(balances.available_balance as i64)
+        .execute(&mut *tx)
+        .await?;
+        
+        tx.commit().await?;
+        
+        Ok(())
+    }
+    
+    /// Overwrites every balance column, used when correcting drift from chain.
+    pub async fn set_vault_balances(&self, vault: &Vault) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE vaults
+            SET total_balance = $2, locked_balance = $3, available_balance = $4,
+                total_deposited = $5, total_withdrawn = $6
+            WHERE owner = $1
+            "#,
+        )
+        .bind(&vault.owner)
+        .bind(vault.total_balance)
+        .bind(vault.locked_balance)
+        .bind(vault.available_balance)
+        .bind(vault.total_deposited)
+        .bind(vault.total_withdrawn)
+        .execute(self.inner())
+        .await
+        .context("Failed to update vault balances")?;
+        
+        Ok(())
+    }
+}
@@ -3,7 +3,9 @@ use collateral_vault_backend::api;
 use collateral_vault_backend::config::Config;
 use collateral_vault_backend::database::DatabasePool;
 use collateral_vault_backend::services;
+use collateral_vault_backend::utils::idl::Idl;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 #[tokio::main]
@@ -23,24 +25,118 @@ async fn main() -> anyhow::Result<()> {
     // Initialize database pool
     let db_pool = DatabasePool::new(&config.database_url).await?;
     
+    // Load the program IDL used to encode instructions
+    let idl = Idl::from_file(&config.idl_path)?;
+    
     // Initialize services
-    let rpc_service = services::rpc::RpcService::new(&config.rpc_url)?;
+    let rpc_service = services::rpc::RpcService::new(&config.rpc_urls)?;
+    
+    // Keep endpoint health and slot lag current so calls go to the best node
+    tokio::spawn(
+        rpc_service.pool().clone()
+            .run_health_checks(Duration::from_secs(config.rpc_health_check_interval_secs)),
+    );
+    
+    // Broadcast new vault events to stream subscribers
+    let event_hub = services::event_hub::EventHub::new(db_pool.clone(), config.event_stream_buffer);
+    
     let vault_service = services::vault::VaultService::new(
         db_pool.clone(),
         rpc_service,
-        config.program_id,
-        config.admin_keypair_path,
+        config.program_id.clone(),
+        config.admin_keypair_path.clone(),
+        idl,
+        config.send_config(),
+        event_hub.clone(),
     )?;
     
+    // Use the address lookup table for v0 transactions, if one was created
+    vault_service
+        .load_configured_lookup_table(config.lookup_table_address.as_deref())
+        .await?;
+    
+    // `backfill` subcommand: rebuild history from chain and exit
+    if std::env::args().nth(1).as_deref() == Some("backfill") {
+        let backfill = services::backfill::Backfill::new(
+            db_pool.clone(),
+            vault_service.rpc_service().clone(),
+            vault_service.program_id(),
+        );
+        let summary = backfill.run().await?;
+        tracing::info!("Backfill finished: {:?}", summary);
+        return Ok(());
+    }
+    
+    // Follow sent transactions until they settle
+    let tracker = services::tracker::TransactionTracker::new(
+        db_pool.clone(),
+        vault_service.rpc_service().clone(),
+    );
+    tokio::spawn(tracker.run(Duration::from_secs(config.tx_poll_interval_secs)));
+    
+    // Keep cached vault balances in line with the chain
+    let reconciler = services::reconciler::Reconciler::new(db_pool.clone(), vault_service.clone());
+    tokio::spawn(reconciler.run(Duration::from_secs(config.reconcile_interval_secs)));
+    
+    // Return durable nonce reservations that were never submitted
+    let nonce_reaper = services::nonce::NonceReaper::new(
+        vault_service.clone(),
+        Duration::from_secs(config.nonce_reservation_ttl_secs),
+    );
+    tokio::spawn(nonce_reaper.run(Duration::from_secs(60)));
+    
+    // Index program activity from any client over websocket
+    let indexer = services::indexer::Indexer::new(db_pool.clone(), &config.ws_url, &config.program_id)?;
+    tokio::spawn(indexer.run());
+    
+    // Feed it from database notifications on new events
+    tokio::spawn(event_hub.run());
+    
+    // Forget idempotency keys once their responses are no longer replayed
+    tokio::spawn(api::idempotency::run_cleanup(
+        db_pool.clone(),
+        Duration::from_secs(config.idempotency_key_ttl_secs),
+        Duration::from_secs(3600),
+    ));
+    
+    // Bearer token verification and partner API keys
+    let jwt_auth = api::auth::JwtAuth::from_config(&config)?;
+    let api_key_service = services::api_keys::ApiKeyService::new(
+        db_pool.clone(),
+        Duration::from_secs(config.api_key_cache_ttl_secs),
+    );
+    
+    // Wallet sign-in issuing session tokens
+    let siws_service = services::siws::SiwsService::new(
+        db_pool.clone(),
+        jwt_auth.clone(),
+        config.siws_config(),
+    );
+    
+    // Per-caller request budgets, shared across instances through Redis
+    let rate_limiter = api::rate_limit::RateLimiter::new(&config.redis_url, config.rate_limit_config()).await;
+    
+    let auth_state = api::auth::AuthState {
+        jwt: jwt_auth,
+        api_keys: api_key_service,
+    };
+    
     // Build application with routes
-    let app = api::router::create_router(db_pool, vault_service, config.clone());
+    let app = api::router::create_router(
+        db_pool,
+        vault_service,
+        auth_state,
+        siws_service,
+        rate_limiter,
+        config.clone(),
+    );
     
     // Start server
     let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("Server listening on {}", addr);
     
     Server::bind(&addr)
-        .serve(app.into_make_service())
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
         .await?;
     
     Ok(())
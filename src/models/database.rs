@@ -25,6 +25,8 @@ pub struct VaultEvent {
     pub event_type: String,
     pub data: serde_json::Value,
     pub created_at: DateTime<Utc>,
+    /// Insertion order, assigned by the database.
+    pub seq: i64,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize)]
@@ -38,6 +40,8 @@ pub struct TransactionLog {
     pub block_time: Option<i64>,
     pub fee: Option<i64>,
     pub error_message: Option<String>,
+    pub recent_blockhash: Option<String>,
+    pub nonce_account: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -51,4 +55,67 @@ pub struct AuthorizedProgram {
     pub added_at: DateTime<Utc>,
     pub removed_at: Option<DateTime<Utc>>,
     pub removed_by: Option<String>,
-}
\ No newline at end of file
+}
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct BackfillCheckpoint {
+    pub address: String,
+    /// All-zero signature (`111...1`) once an address with no history is walked.
+    pub newest_signature: Option<String>,
+    pub oldest_signature: Option<String>,
+    pub completed: bool,
+    pub signatures_processed: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct NonceAccount {
+    pub address: String,
+    pub authority: String,
+    pub reserved_at: Option<DateTime<Utc>>,
+    pub signature: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SiwsChallenge {
+    pub nonce: String,
+    pub wallet: String,
+    pub message: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub key_prefix: String,
+    #[serde(skip_serializing)]
+    pub key_hash: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub rotated_from: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Vault owners the key may act for, unless it has the `admin` scope.
+    pub owners: Vec<String>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct IdempotencyKey {
+    pub caller: String,
+    pub key: String,
+    pub method: String,
+    pub path: String,
+    pub request_hash: String,
+    pub status_code: Option<i32>,
+    pub response_body: Option<Vec<u8>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+}
@@ -1,3 +1,4 @@
 pub mod database;
+pub mod onchain;
 pub mod requests;
 pub mod responses;
\ No newline at end of file
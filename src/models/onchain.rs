@@ -0,0 +1,257 @@
+use anchor_client::solana_sdk::pubkey::Pubkey;
+use anchor_lang::AnchorDeserialize;
+use anyhow::{Result, bail};
+use serde::Serialize;
+
+use crate::utils::idl::{account_discriminator, event_discriminator};
+
+/// Anchor account stored in the program, prefixed by an 8-byte discriminator.
+pub trait OnchainAccount: AnchorDeserialize {
+    const NAME: &'static str;
+
+    fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 {
+            bail!("{} account data too short ({} bytes)", Self::NAME, data.len());
+        }
+
+        let (discriminator, mut body) = data.split_at(8);
+        if discriminator != account_discriminator(Self::NAME) {
+            bail!("Account is not a {}: discriminator mismatch", Self::NAME);
+        }
+
+        // Accounts may be allocated with trailing padding, so don't require
+        // the whole buffer to be consumed.
+        Ok(Self::deserialize(&mut body)?)
+    }
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct CollateralVault {
+    pub owner: Pubkey,
+    pub token_account: Pubkey,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl OnchainAccount for CollateralVault {
+    const NAME: &'static str = "CollateralVault";
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct VaultAuthority {
+    pub authorized_programs: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl OnchainAccount for VaultAuthority {
+    const NAME: &'static str = "VaultAuthority";
+}
+
+/// Decoded vault account together with the balance of its token account.
+#[derive(Debug, Clone, Serialize)]
+pub struct OnchainVaultState {
+    pub vault_address: String,
+    pub owner: String,
+    pub token_account: String,
+    pub token_account_balance: u64,
+    pub total_balance: u64,
+    pub locked_balance: u64,
+    pub available_balance: u64,
+    pub total_deposited: u64,
+    pub total_withdrawn: u64,
+    pub created_at: i64,
+}
+
+impl OnchainVaultState {
+    pub fn new(vault_address: Pubkey, vault: &CollateralVault, token_account_balance: u64) -> Self {
+        Self {
+            vault_address: vault_address.to_string(),
+            owner: vault.owner.to_string(),
+            token_account: vault.token_account.to_string(),
+            token_account_balance,
+            total_balance: vault.total_balance,
+            locked_balance: vault.locked_balance,
+            available_balance: vault.available_balance,
+            total_deposited: vault.total_deposited,
+            total_withdrawn: vault.total_withdrawn,
+            created_at: vault.created_at,
+        }
+    }
+}
+
+/// Anchor event emitted with `emit!`, logged as base64 after `Program data: `.
+pub trait OnchainEvent: AnchorDeserialize {
+    const NAME: &'static str;
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct DepositEvent {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub new_balance: u64,
+    pub timestamp: i64,
+}
+
+impl OnchainEvent for DepositEvent {
+    const NAME: &'static str = "DepositEvent";
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct WithdrawEvent {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub new_balance: u64,
+    pub timestamp: i64,
+}
+
+impl OnchainEvent for WithdrawEvent {
+    const NAME: &'static str = "WithdrawEvent";
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct LockEvent {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub caller_program: Pubkey,
+    pub new_locked_balance: u64,
+    pub timestamp: i64,
+}
+
+impl OnchainEvent for LockEvent {
+    const NAME: &'static str = "LockEvent";
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct UnlockEvent {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub caller_program: Pubkey,
+    pub new_locked_balance: u64,
+    pub timestamp: i64,
+}
+
+impl OnchainEvent for UnlockEvent {
+    const NAME: &'static str = "UnlockEvent";
+}
+
+#[derive(Debug, Clone, AnchorDeserialize)]
+pub struct TransferEvent {
+    pub from_owner: Pubkey,
+    pub to_owner: Pubkey,
+    pub amount: u64,
+    pub caller_program: Pubkey,
+    pub timestamp: i64,
+}
+
+impl OnchainEvent for TransferEvent {
+    const NAME: &'static str = "TransferEvent";
+}
+
+#[derive(Debug, Clone)]
+pub enum ProgramEvent {
+    Deposit(DepositEvent),
+    Withdraw(WithdrawEvent),
+    Lock(LockEvent),
+    Unlock(UnlockEvent),
+    Transfer(TransferEvent),
+}
+
+impl ProgramEvent {
+    /// Decodes an event payload, returning `None` for events we don't track.
+    pub fn decode(data: &[u8]) -> Result<Option<Self>> {
+        if data.len() < 8 {
+            bail!("Event data too short ({} bytes)", data.len());
+        }
+
+        let (discriminator, body) = data.split_at(8);
+
+        let event = if discriminator == event_discriminator(DepositEvent::NAME) {
+            Self::Deposit(decode_event(body)?)
+        } else if discriminator == event_discriminator(WithdrawEvent::NAME) {
+            Self::Withdraw(decode_event(body)?)
+        } else if discriminator == event_discriminator(LockEvent::NAME) {
+            Self::Lock(decode_event(body)?)
+        } else if discriminator == event_discriminator(UnlockEvent::NAME) {
+            Self::Unlock(decode_event(body)?)
+        } else if discriminator == event_discriminator(TransferEvent::NAME) {
+            Self::Transfer(decode_event(body)?)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(event))
+    }
+
+    pub fn transaction_type(&self) -> &'static str {
+        match self {
+            Self::Deposit(_) => "deposit",
+            Self::Withdraw(_) => "withdraw",
+            Self::Lock(_) => "lock",
+            Self::Unlock(_) => "unlock",
+            Self::Transfer(_) => "transfer",
+        }
+    }
+
+    /// The owner a transaction log row is attributed to.
+    pub fn primary_owner(&self) -> Pubkey {
+        match self {
+            Self::Deposit(e) => e.owner,
+            Self::Withdraw(e) => e.owner,
+            Self::Lock(e) => e.owner,
+            Self::Unlock(e) => e.owner,
+            Self::Transfer(e) => e.from_owner,
+        }
+    }
+
+    /// `(owner, event_type, data)` rows to record in `vault_events`. Transfers
+    /// produce one row per side, matching what `VaultService` logs.
+    pub fn vault_events(&self) -> Vec<(String, &'static str, serde_json::Value)> {
+        match self {
+            Self::Deposit(e) => vec![(e.owner.to_string(), "deposit", serde_json::json!({
+                "amount": e.amount,
+                "new_balance": e.new_balance,
+                "timestamp": e.timestamp,
+            }))],
+            Self::Withdraw(e) => vec![(e.owner.to_string(), "withdraw", serde_json::json!({
+                "amount": e.amount,
+                "new_balance": e.new_balance,
+                "timestamp": e.timestamp,
+            }))],
+            Self::Lock(e) => vec![(e.owner.to_string(), "lock", serde_json::json!({
+                "amount": e.amount,
+                "caller_program": e.caller_program.to_string(),
+                "new_locked_balance": e.new_locked_balance,
+                "timestamp": e.timestamp,
+            }))],
+            Self::Unlock(e) => vec![(e.owner.to_string(), "unlock", serde_json::json!({
+                "amount": e.amount,
+                "caller_program": e.caller_program.to_string(),
+                "new_locked_balance": e.new_locked_balance,
+                "timestamp": e.timestamp,
+            }))],
+            Self::Transfer(e) => vec![
+                (e.from_owner.to_string(), "transfer_out", serde_json::json!({
+                    "amount": e.amount,
+                    "to_owner": e.to_owner.to_string(),
+                    "caller_program": e.caller_program.to_string(),
+                    "timestamp": e.timestamp,
+                })),
+                (e.to_owner.to_string(), "transfer_in", serde_json::json!({
+                    "amount": e.amount,
+                    "from_owner": e.from_owner.to_string(),
+                    "caller_program": e.caller_program.to_string(),
+                    "timestamp": e.timestamp,
+                })),
+            ],
+        }
+    }
+}
+
+fn decode_event<T: OnchainEvent>(mut body: &[u8]) -> Result<T> {
+    Ok(T::deserialize(&mut body)?)
+}
@@ -1,6 +1,15 @@
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityLevel {
+    Low,
+    Medium,
+    High,
+    Max,
+}
+
 #[derive(Debug, Deserialize, Validate)]
 pub struct CreateVaultRequest {
     #[validate(length(min = 32, max = 44))]
@@ -19,6 +28,8 @@ pub struct DepositRequest {
     pub user_token_account: String,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -30,6 +41,8 @@ pub struct WithdrawRequest {
     pub user_token_account: String,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -41,6 +54,8 @@ pub struct LockRequest {
     pub caller_program: String,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -52,6 +67,8 @@ pub struct UnlockRequest {
     pub caller_program: String,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -66,6 +83,8 @@ pub struct TransferRequest {
     pub caller_program: String,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -74,6 +93,8 @@ pub struct InitializeAuthorityRequest {
     pub authorized_programs: Vec<String>,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -82,16 +103,284 @@ pub struct AddAuthorizedProgramRequest {
     pub program: String,
     
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateNonceAccountsRequest {
+    #[validate(range(min = 1, max = 20))]
+    pub count: usize,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ExtendLookupTableRequest {
+    /// A single extension may add at most 256 addresses to a table.
+    #[validate(length(min = 1, max = 256))]
+    pub addresses: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct BuildTransactionRequest {
     pub parameters: serde_json::Value,
+    
+    /// Pays the transaction fee; defaults to the instruction's signer.
+    #[validate(length(min = 32, max = 44))]
+    pub fee_payer: Option<String>,
+    
+    /// Use a durable nonce so the transaction doesn't expire while it waits
+    /// for signatures. Each one holds a nonce account from the shared pool
+    /// until it lands, so only operators may ask for it.
+    #[serde(default)]
+    pub durable: bool,
+    
     pub priority_fee: Option<u64>,
+    
+    pub priority_level: Option<PriorityLevel>,
+}
+
+/// Parameters of `/transactions/build/:tx_type`, one variant per program
+/// instruction.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "tx_type", content = "parameters", rename_all = "snake_case")]
+pub enum BuildParameters {
+    InitializeVault {
+        owner: String,
+        token_mint: String,
+    },
+    Deposit {
+        owner: String,
+        amount: u64,
+        user_token_account: String,
+    },
+    Withdraw {
+        owner: String,
+        amount: u64,
+        user_token_account: String,
+    },
+    LockCollateral {
+        owner: String,
+        amount: u64,
+        caller_program: String,
+    },
+    UnlockCollateral {
+        owner: String,
+        amount: u64,
+        caller_program: String,
+    },
+    TransferCollateral {
+        from_owner: String,
+        to_owner: String,
+        amount: u64,
+        caller_program: String,
+    },
+    CloseVault {
+        owner: String,
+    },
+    InitializeAuthority {
+        authorized_programs: Vec<String>,
+    },
+    AddAuthorizedProgram {
+        program: String,
+    },
+    RemoveAuthorizedProgram {
+        program: String,
+    },
+}
+
+impl BuildParameters {
+    /// Owners of the vaults the instruction acts on. The receiving side of a
+    /// transfer is not included; it is credited, not debited.
+    pub fn owners(&self) -> Vec<&str> {
+        match self {
+            BuildParameters::InitializeVault { owner, .. }
+            | BuildParameters::Deposit { owner, .. }
+            | BuildParameters::Withdraw { owner, .. }
+            | BuildParameters::LockCollateral { owner, .. }
+            | BuildParameters::UnlockCollateral { owner, .. }
+            | BuildParameters::CloseVault { owner } => vec![owner.as_str()],
+            BuildParameters::TransferCollateral { from_owner, .. } => vec![from_owner.as_str()],
+            BuildParameters::InitializeAuthority { .. }
+            | BuildParameters::AddAuthorizedProgram { .. }
+            | BuildParameters::RemoveAuthorizedProgram { .. } => Vec::new(),
+        }
+    }
+    
+    pub fn tx_type(&self) -> &'static str {
+        match self {
+            BuildParameters::InitializeVault { .. } => "initialize_vault",
+            BuildParameters::Deposit { .. } => "deposit",
+            BuildParameters::Withdraw { .. } => "withdraw",
+            BuildParameters::LockCollateral { .. } => "lock_collateral",
+            BuildParameters::UnlockCollateral { .. } => "unlock_collateral",
+            BuildParameters::TransferCollateral { .. } => "transfer_collateral",
+            BuildParameters::CloseVault { .. } => "close_vault",
+            BuildParameters::InitializeAuthority { .. } => "initialize_authority",
+            BuildParameters::AddAuthorizedProgram { .. } => "add_authorized_program",
+            BuildParameters::RemoveAuthorizedProgram { .. } => "remove_authorized_program",
+        }
+    }
+    
+    pub fn parse(tx_type: &str, parameters: serde_json::Value) -> Result<Self, serde_json::Error> {
+        serde_json::from_value(serde_json::json!({
+            "tx_type": tx_type,
+            "parameters": parameters,
+        }))
+    }
 }
 
 #[derive(Debug, Deserialize, Validate)]
 pub struct SubmitTransactionRequest {
-    #[validate(length(min = 88, max = 176))]
+    /// Base64-encoded, fully signed transaction (at most 1232 bytes). Before
+    /// build/submit this was bs58; see CHANGELOG.md.
+    #[validate(length(min = 1, max = 1644))]
     pub signed_transaction: String,
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeeEstimateQuery {
+    /// Comma-separated writable accounts to scope the estimate to, at most
+    /// `MAX_FEE_ACCOUNTS`.
+    pub accounts: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SiwsChallengeRequest {
+    #[validate(length(min = 32, max = 44))]
+    pub wallet: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SiwsVerifyRequest {
+    /// The challenge message exactly as it was signed.
+    #[validate(length(min = 1, max = 1024))]
+    pub message: String,
+    /// Base58-encoded ed25519 signature over `message`.
+    #[validate(length(min = 64, max = 88))]
+    pub signature: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApiKeyRequest {
+    /// Partner or owner the key is issued to.
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<String>,
+    /// Vault owners the key may act for. Keys with the `admin` scope act for
+    /// every owner and need none.
+    #[serde(default)]
+    #[validate(length(max = 1000))]
+    pub owners: Vec<String>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultSortField {
+    #[default]
+    CreatedAt,
+    TotalBalance,
+    LockedBalance,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VaultListQuery {
+    #[validate(range(min = 1))]
+    pub page: Option<u32>,
+    #[validate(range(min = 1, max = 200))]
+    pub page_size: Option<u32>,
+    /// `next_cursor` of the previous page; takes precedence over `page`.
+    pub cursor: Option<String>,
+    pub token_mint: Option<String>,
+    #[validate(range(min = 0))]
+    pub min_total_balance: Option<i64>,
+    #[validate(range(min = 0))]
+    pub max_total_balance: Option<i64>,
+    #[validate(range(min = 0))]
+    pub min_locked_balance: Option<i64>,
+    #[validate(range(min = 0))]
+    pub max_locked_balance: Option<i64>,
+    pub created_after: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_before: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub sort_by: VaultSortField,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// Event types a vault's history can be filtered by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultEventType {
+    Deposit,
+    Withdraw,
+    Lock,
+    Unlock,
+    TransferIn,
+    TransferOut,
+    Close,
+}
+
+impl VaultEventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VaultEventType::Deposit => "deposit",
+            VaultEventType::Withdraw => "withdraw",
+            VaultEventType::Lock => "lock",
+            VaultEventType::Unlock => "unlock",
+            VaultEventType::TransferIn => "transfer_in",
+            VaultEventType::TransferOut => "transfer_out",
+            VaultEventType::Close => "close",
+        }
+    }
+    
+    pub fn parse(event_type: &str) -> Option<Self> {
+        match event_type {
+            "deposit" => Some(VaultEventType::Deposit),
+            "withdraw" => Some(VaultEventType::Withdraw),
+            "lock" => Some(VaultEventType::Lock),
+            "unlock" => Some(VaultEventType::Unlock),
+            "transfer_in" => Some(VaultEventType::TransferIn),
+            "transfer_out" => Some(VaultEventType::TransferOut),
+            "close" => Some(VaultEventType::Close),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct VaultEventQuery {
+    #[validate(range(min = 1, max = 200))]
+    pub limit: Option<u32>,
+    /// `next_cursor` of the previous page.
+    pub cursor: Option<String>,
+    /// Comma-separated event types, e.g. `lock,unlock`.
+    pub event_type: Option<String>,
+    pub after: Option<chrono::DateTime<chrono::Utc>>,
+    pub before: Option<chrono::DateTime<chrono::Utc>>,
+    #[validate(length(min = 64, max = 88))]
+    pub signature: Option<String>,
+    /// Only on `/events`; the per-vault route takes the owner from the path.
+    #[validate(length(min = 32, max = 44))]
+    pub owner: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct EventStreamQuery {
+    /// Defaults to the caller's own vault for wallet sessions.
+    #[validate(length(min = 32, max = 44))]
+    pub owner: Option<String>,
+    /// Comma-separated event types, e.g. `lock,unlock`.
+    pub event_type: Option<String>,
+    #[validate(length(min = 32, max = 44))]
+    pub caller_program: Option<String>,
+}
@@ -1,5 +1,9 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use crate::models::database::{ApiKey, BackfillCheckpoint, NonceAccount, VaultEvent};
+use crate::models::onchain::OnchainVaultState;
 
 #[derive(Debug, Serialize)]
 pub struct HealthResponse {
@@ -8,13 +12,6 @@ pub struct HealthResponse {
     pub version: String,
 }
 
-#[derive(Debug, Serialize)]
-pub struct CreateVaultResponse {
-    pub vault_address: String,
-    pub transaction: String,
-    pub message: String,
-}
-
 #[derive(Debug, Serialize)]
 pub struct VaultResponse {
     pub owner: String,
@@ -28,6 +25,67 @@ pub struct VaultResponse {
     pub token_mint: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct OnchainVaultResponse {
+    pub owner: String,
+    pub database: Option<VaultResponse>,
+    pub onchain: OnchainVaultState,
+    pub in_sync: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NonceAccountsResponse {
+    pub nonce_accounts: Vec<NonceAccount>,
+    pub available: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LookupTableResponse {
+    pub address: String,
+    pub addresses: Vec<String>,
+    /// Transactions sent by the request, empty for reads.
+    pub signatures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceDiff {
+    pub database: i64,
+    pub onchain: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultDrift {
+    pub owner: String,
+    pub vault_address: String,
+    pub fields: BTreeMap<String, BalanceDiff>,
+    pub token_account_balance: Option<u64>,
+    /// Token account balance minus the vault's on-chain `total_balance`,
+    /// when they differ. Rewriting the database can't fix this.
+    pub token_account_drift: Option<i64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub checked_at: DateTime<Utc>,
+    pub vaults_checked: usize,
+    pub corrected: bool,
+    pub drifted: Vec<VaultDrift>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BackfillSummary {
+    pub vaults_discovered: usize,
+    pub addresses_walked: usize,
+    pub signatures_processed: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackfillStatusResponse {
+    pub running: bool,
+    pub checkpoints: Vec<BackfillCheckpoint>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct TransactionResponse {
     pub transaction: String,
@@ -36,6 +94,43 @@ pub struct TransactionResponse {
     pub message: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BuildTransactionResponse {
+    /// Base64-encoded transaction; only the server's signatures are filled in.
+    pub transaction: String,
+    /// `legacy` or `0`; v0 is used when a lookup table shrinks the message.
+    pub version: String,
+    pub fee_payer: String,
+    pub required_signers: Vec<String>,
+    /// The nonce value for durable transactions.
+    pub recent_blockhash: String,
+    pub last_valid_block_height: Option<u64>,
+    pub nonce_account: Option<String>,
+    pub estimated_fee: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SiwsChallengeResponse {
+    pub message: String,
+    pub nonce: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub token: String,
+    pub wallet: String,
+    pub roles: Vec<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Returned when a key is created or rotated; the only time `key` is shown.
+#[derive(Debug, Serialize)]
+pub struct ApiKeySecretResponse {
+    pub api_key: ApiKey,
+    pub key: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct TransactionStatusResponse {
     pub signature: String,
@@ -52,6 +147,16 @@ pub struct VaultListResponse {
     pub total: usize,
     pub page: u32,
     pub page_size: u32,
+    /// Pass as `cursor` to fetch the next page; absent on the last page.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VaultEventListResponse {
+    /// Newest first.
+    pub events: Vec<VaultEvent>,
+    /// Pass as `cursor` to fetch older events; absent on the last page.
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
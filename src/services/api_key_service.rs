@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use solana_sdk::hash::hash;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::database::DatabasePool;
+use crate::models::database::ApiKey;
+
+pub mod scopes {
+    pub const VAULT_READ: &str = "vault:read";
+    pub const VAULT_WRITE: &str = "vault:write";
+    pub const COLLATERAL_LOCK: &str = "collateral:lock";
+    /// Grants every other scope.
+    pub const ADMIN: &str = "admin";
+
+    pub const ALL: &[&str] = &[VAULT_READ, VAULT_WRITE, COLLATERAL_LOCK, ADMIN];
+}
+
+const KEY_PREFIX: &str = "cvk_";
+
+/// Characters of the plaintext key kept in `key_prefix` for identification.
+const VISIBLE_PREFIX_LEN: usize = 12;
+
+/// Partner API keys stored hashed in the database. Resolved keys are cached
+/// briefly so authenticating a request doesn't cost a query every time.
+#[derive(Clone)]
+pub struct ApiKeyService {
+    db_pool: DatabasePool,
+    cache: Arc<RwLock<HashMap<String, (ApiKey, Instant)>>>,
+    cache_ttl: Duration,
+}
+
+impl ApiKeyService {
+    pub fn new(db_pool: DatabasePool, cache_ttl: Duration) -> Self {
+        Self {
+            db_pool,
+            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache_ttl,
+        }
+    }
+
+    /// Creates a key and returns it with its plaintext, which is never stored.
+    pub async fn create(
+        &self,
+        name: &str,
+        scopes: &[String],
+        owners: &[String],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(ApiKey, String)> {
+        self.issue(name, scopes, owners, expires_at, None).await
+    }
+
+    /// Revokes `id` and issues a replacement with the same name, scopes,
+    /// owners and expiry. Returns `None` if there is no unrevoked key with
+    /// that id.
+    pub async fn rotate(&self, id: Uuid) -> Result<Option<(ApiKey, String)>> {
+        let current = match self.db_pool.get_api_key(id).await? {
+            Some(key) if key.revoked_at.is_none() => key,
+            _ => return Ok(None),
+        };
+
+        let issued = self
+            .issue(&current.name, &current.scopes, &current.owners, current.expires_at, Some(id))
+            .await?;
+        self.revoke(id).await?;
+
+        info!("Rotated API key {} ({}) to {}", id, current.name, issued.0.id);
+        Ok(Some(issued))
+    }
+
+    /// Revokes `id`. Only this instance's cache is cleared: other instances
+    /// keep accepting the key until their cached copy is older than
+    /// `API_KEY_CACHE_TTL_SECS`.
+    pub async fn revoke(&self, id: Uuid) -> Result<Option<ApiKey>> {
+        let key = self.db_pool.revoke_api_key(id).await?;
+
+        if let Some(key) = &key {
+            self.cache.write().await.remove(&key.key_hash);
+        }
+
+        Ok(key)
+    }
+
+    pub async fn list(&self) -> Result<Vec<ApiKey>> {
+        self.db_pool.list_api_keys().await
+    }
+
+    /// Returns the active key matching `plaintext`, if any.
+    pub async fn resolve(&self, plaintext: &str) -> Result<Option<ApiKey>> {
+        let key_hash = hash_key(plaintext);
+
+        if let Some((key, cached_at)) = self.cache.read().await.get(&key_hash) {
+            let expired = key.expires_at.map_or(false, |expires_at| expires_at <= Utc::now());
+            if cached_at.elapsed() < self.cache_ttl && !expired {
+                return Ok(Some(key.clone()));
+            }
+        }
+
+        // Misses aren't cached so unknown keys can't grow the cache
+        let Some(key) = self.db_pool.find_active_api_key(&key_hash).await? else {
+            self.cache.write().await.remove(&key_hash);
+            return Ok(None);
+        };
+
+        let db_pool = self.db_pool.clone();
+        let id = key.id;
+        tokio::spawn(async move {
+            if let Err(e) = db_pool.touch_api_key(id).await {
+                warn!("Failed to record API key use for {}: {:?}", id, e);
+            }
+        });
+
+        self.cache.write().await.insert(key_hash, (key.clone(), Instant::now()));
+        Ok(Some(key))
+    }
+
+    async fn issue(
+        &self,
+        name: &str,
+        scopes: &[String],
+        owners: &[String],
+        expires_at: Option<DateTime<Utc>>,
+        rotated_from: Option<Uuid>,
+    ) -> Result<(ApiKey, String)> {
+        let plaintext = format!(
+            "{}{}{}",
+            KEY_PREFIX,
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple(),
+        );
+
+        let key = self.db_pool
+            .insert_api_key(
+                name,
+                &plaintext[..VISIBLE_PREFIX_LEN],
+                &hash_key(&plaintext),
+                scopes,
+                owners,
+                expires_at,
+                rotated_from,
+            )
+            .await?;
+
+        Ok((key, plaintext))
+    }
+}
+
+fn hash_key(plaintext: &str) -> String {
+    hex::encode(hash(plaintext.as_bytes()).to_bytes())
+}
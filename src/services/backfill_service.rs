@@ -0,0 +1,246 @@
+use std::str::FromStr;
+use anchor_client::solana_sdk::{pubkey::Pubkey, signature::Signature};
+use anyhow::{Result, Context, bail};
+use chrono::TimeZone;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use tracing::{info, warn};
+
+use crate::database::DatabasePool;
+use crate::database::checkpoints::BackfillLock;
+use crate::services::indexer::EventRecorder;
+use crate::services::rpc::RpcService;
+use crate::models::{
+    database::Vault,
+    onchain::{CollateralVault, OnchainAccount},
+    responses::BackfillSummary,
+};
+use crate::utils::idl::account_discriminator;
+
+const PAGE_SIZE: usize = 1000;
+
+/// Rebuilds `vaults`, `vault_events` and `transaction_logs` from chain
+/// history. Progress is checkpointed per address after every page, so an
+/// interrupted run resumes where it stopped. Only one backfill runs at a
+/// time across every instance, guarded by a database advisory lock.
+#[derive(Clone)]
+pub struct Backfill {
+    db_pool: DatabasePool,
+    rpc_service: RpcService,
+    program_id: Pubkey,
+    recorder: EventRecorder,
+}
+
+impl Backfill {
+    pub fn new(db_pool: DatabasePool, rpc_service: RpcService, program_id: Pubkey) -> Self {
+        Self {
+            recorder: EventRecorder::new(db_pool.clone(), program_id),
+            db_pool,
+            rpc_service,
+            program_id,
+        }
+    }
+
+    pub async fn run(&self) -> Result<BackfillSummary> {
+        let Some(lock) = self.db_pool.try_lock_backfill().await? else {
+            bail!("A backfill is already running");
+        };
+
+        self.run_locked(lock).await
+    }
+
+    /// Runs with a lock the caller already took, and releases it after.
+    pub async fn run_locked(&self, lock: BackfillLock) -> Result<BackfillSummary> {
+        let result = self.run_inner().await;
+        lock.release().await;
+
+        result
+    }
+
+    async fn run_inner(&self) -> Result<BackfillSummary> {
+        let (vaults_discovered, vault_addresses) = self.discover_vaults().await?;
+
+        let mut addresses = vec![self.program_id];
+        addresses.extend(vault_addresses);
+
+        let mut signatures_processed = 0;
+        for address in &addresses {
+            signatures_processed += self.backfill_address(address).await?;
+        }
+
+        info!(
+            "Backfill complete: {} new vaults, {} addresses, {} signatures",
+            vaults_discovered,
+            addresses.len(),
+            signatures_processed,
+        );
+
+        Ok(BackfillSummary {
+            vaults_discovered,
+            addresses_walked: addresses.len(),
+            signatures_processed,
+        })
+    }
+
+    /// Loads every vault account owned by the program and inserts rows for
+    /// the ones the database doesn't know. Returns the number inserted and
+    /// the addresses of all vaults to walk.
+    async fn discover_vaults(&self) -> Result<(usize, Vec<Pubkey>)> {
+        let accounts = self.rpc_service
+            .get_program_accounts_with_discriminator(
+                &self.program_id,
+                account_discriminator(CollateralVault::NAME),
+            )
+            .await?;
+
+        let mut inserted = 0;
+        let mut addresses = Vec::with_capacity(accounts.len());
+
+        for (address, data) in accounts {
+            let vault = match CollateralVault::decode(&data) {
+                Ok(vault) => vault,
+                Err(e) => {
+                    warn!("Skipping undecodable vault account {}: {:?}", address, e);
+                    continue;
+                }
+            };
+
+            let token_mint = self.rpc_service.get_token_account_mint(&vault.token_account).await?;
+            let created_at = chrono::Utc
+                .timestamp_opt(vault.created_at, 0)
+                .single()
+                .unwrap_or_else(chrono::Utc::now);
+
+            let stored = self.db_pool.store_vault_if_missing(Vault {
+                id: uuid::Uuid::new_v4(),
+                owner: vault.owner.to_string(),
+                vault_address: address.to_string(),
+                token_mint: token_mint.to_string(),
+                total_balance: vault.total_balance as i64,
+                locked_balance: vault.locked_balance as i64,
+                available_balance: vault.available_balance as i64,
+                total_deposited: vault.total_deposited as i64,
+                total_withdrawn: vault.total_withdrawn as i64,
+                created_at,
+                updated_at: chrono::Utc::now(),
+            }).await?;
+
+            if stored {
+                inserted += 1;
+            }
+            addresses.push(address);
+        }
+
+        Ok((inserted, addresses))
+    }
+
+    /// Walks an address's signatures newest to oldest. A completed address
+    /// only catches up on signatures newer than its checkpointed head.
+    async fn backfill_address(&self, address: &Pubkey) -> Result<u64> {
+        let key = address.to_string();
+        let checkpoint = self.db_pool.get_backfill_checkpoint(&key).await?;
+
+        if let Some(checkpoint) = checkpoint.as_ref().filter(|c| c.completed) {
+            let until = checkpoint.newest_signature
+                .as_deref()
+                .map(Signature::from_str)
+                .transpose()?
+                .filter(|head| *head != Signature::default());
+
+            return self.catch_up(address, until).await;
+        }
+
+        let mut before = checkpoint
+            .and_then(|c| c.oldest_signature)
+            .as_deref()
+            .map(Signature::from_str)
+            .transpose()?;
+        let mut processed = 0;
+
+        loop {
+            let page = self.rpc_service
+                .get_signatures_for_address(address, before, None, PAGE_SIZE)
+                .await?;
+
+            let Some(last) = page.last() else {
+                // An address with no history gets the all-zero signature as
+                // its head, which never lands, so it's told apart from an
+                // unwalked one and catch-up walks everything it gets later.
+                let empty_head = Signature::default().to_string();
+                self.db_pool.save_backfill_checkpoint(&key, Some(&empty_head), None, 0, true).await?;
+                break;
+            };
+
+            self.process_page(&page).await?;
+            processed += page.len() as u64;
+
+            self.db_pool.save_backfill_checkpoint(
+                &key,
+                Some(&page[0].signature),
+                Some(&last.signature),
+                page.len() as u64,
+                false,
+            ).await?;
+
+            info!("Backfilled {} signatures for {} (at {})", processed, key, last.signature);
+
+            before = Some(Signature::from_str(&last.signature)?);
+        }
+
+        Ok(processed)
+    }
+
+    async fn catch_up(&self, address: &Pubkey, until: Option<Signature>) -> Result<u64> {
+        let key = address.to_string();
+        let mut before = None;
+        let mut head = None;
+        let mut processed = 0;
+
+        loop {
+            let page = self.rpc_service
+                .get_signatures_for_address(address, before, until, PAGE_SIZE)
+                .await?;
+
+            let Some(last) = page.last() else {
+                break;
+            };
+
+            if head.is_none() {
+                head = Some(page[0].signature.clone());
+            }
+
+            self.process_page(&page).await?;
+            processed += page.len() as u64;
+            before = Some(Signature::from_str(&last.signature)?);
+        }
+
+        // Only move the head once everything newer has been processed, so an
+        // interrupted catch-up is simply repeated.
+        if let Some(head) = head {
+            self.db_pool.advance_backfill_head(&key, &head, processed).await?;
+        }
+
+        Ok(processed)
+    }
+
+    async fn process_page(&self, page: &[RpcConfirmedTransactionStatusWithSignature]) -> Result<()> {
+        for entry in page {
+            let signature = Signature::from_str(&entry.signature)?;
+            let tx = self.rpc_service
+                .get_transaction_logs(&signature)
+                .await
+                .with_context(|| format!("Failed to fetch transaction {}", entry.signature))?;
+
+            self.recorder.record_transaction(
+                &entry.signature,
+                "finalized",
+                tx.slot,
+                tx.block_time,
+                Some(tx.fee),
+                &tx.logs,
+                tx.error.as_deref(),
+            ).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::{Result, Context};
+use axum::response::sse::Event;
+use futures::stream::{self, Stream, StreamExt};
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn, error, debug};
+use uuid::Uuid;
+
+use crate::database::DatabasePool;
+use crate::database::events::VaultEventFilter;
+use crate::models::database::VaultEvent;
+
+/// Channel the `vault_events` insert trigger notifies with the new row's id.
+pub const VAULT_EVENTS_CHANNEL: &str = "vault_events";
+
+/// Most events replayed to a resuming subscriber in one go.
+const MAX_REPLAY: i64 = 500;
+
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Fans `vault_events` inserts out to stream subscribers. One database
+/// listener per instance feeds a broadcast channel; a subscriber that falls
+/// more than `capacity` events behind is dropped rather than buffered for.
+#[derive(Clone)]
+pub struct EventHub {
+    db_pool: DatabasePool,
+    sender: broadcast::Sender<Arc<VaultEvent>>,
+}
+
+impl EventHub {
+    pub fn new(db_pool: DatabasePool, capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+
+        Self {
+            db_pool,
+            sender,
+        }
+    }
+
+    pub async fn run(self) {
+        let mut delay = Duration::from_secs(1);
+
+        loop {
+            match self.listen().await {
+                Ok(()) => {
+                    warn!("Listener on {} lost its connection, reconnecting", VAULT_EVENTS_CHANNEL);
+                    delay = Duration::from_secs(1);
+                }
+                Err(e) => {
+                    error!("Listener on {} failed: {:?}", VAULT_EVENTS_CHANNEL, e);
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                }
+            }
+
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    /// Forwards notifications until the connection drops. Events inserted
+    /// while disconnected aren't broadcast; subscribers get them on resume.
+    async fn listen(&self) -> Result<()> {
+        let mut listener = PgListener::connect_with(self.db_pool.inner())
+            .await
+            .context("Failed to connect event listener")?;
+        listener.listen(VAULT_EVENTS_CHANNEL).await?;
+
+        info!("Listening for vault events on {}", VAULT_EVENTS_CHANNEL);
+
+        while let Some(notification) = listener.try_recv().await? {
+            let Ok(id) = Uuid::parse_str(notification.payload()) else {
+                warn!("Ignoring malformed vault event notification: {}", notification.payload());
+                continue;
+            };
+
+            match self.db_pool.get_vault_event(id).await? {
+                // No receivers is not an error; there is just nobody streaming
+                Some(event) => {
+                    let _ = self.sender.send(Arc::new(event));
+                }
+                None => debug!("Vault event {} is gone before it could be broadcast", id),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Streams events matching `filter`. With `last_event_id`, the `seq` of
+    /// the last event the client got, first replays what came after it from
+    /// the table.
+    pub async fn subscribe(
+        &self,
+        filter: VaultEventFilter,
+        last_event_id: Option<i64>,
+    ) -> Result<impl Stream<Item = Result<Event, Infallible>>> {
+        // Subscribe before replaying so nothing inserted meanwhile is missed
+        let receiver = self.sender.subscribe();
+
+        let replay = match last_event_id {
+            Some(seq) => self.db_pool.list_vault_events_since(&filter, seq, MAX_REPLAY).await?,
+            None => Vec::new(),
+        };
+
+        // A full replay may not be everything that was missed. End the stream
+        // after it so the client reconnects from its last event and continues.
+        let live = (replay.len() as i64) < MAX_REPLAY;
+        let replayed: HashSet<i64> = replay.iter().map(|event| event.seq).collect();
+
+        let replay = stream::iter(replay.into_iter().map(|event| Ok(sse_event(&event))));
+
+        let live = stream::unfold(live.then_some((receiver, replayed, filter)), |state| async move {
+            let (mut receiver, replayed, filter) = state?;
+
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if filter.matches(&event) && !replayed.contains(&event.seq) => {
+                        return Some((Ok(sse_event(&event)), Some((receiver, replayed, filter))));
+                    }
+                    Ok(_) => continue,
+                    // Too slow to keep up: drop the client instead of buffering
+                    // for it. It resumes from the table with Last-Event-ID.
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!("Dropping event stream subscriber {} events behind", skipped);
+                        let lagged = Event::default().event("lagged").data(skipped.to_string());
+                        return Some((Ok(lagged), None));
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        Ok(replay.chain(live))
+    }
+}
+
+fn sse_event(event: &VaultEvent) -> Event {
+    Event::default()
+        .id(event.seq.to_string())
+        .data(serde_json::to_string(event).unwrap_or_default())
+}
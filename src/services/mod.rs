@@ -1,3 +1,14 @@
 pub mod vault;
 pub mod transaction;
-pub mod rpc;
\ No newline at end of file
+pub mod rpc;
+pub mod rpc_pool;
+pub mod reconciler;
+pub mod indexer;
+pub mod event_hub;
+pub mod backfill;
+pub mod tracker;
+pub mod sender;
+pub mod policy;
+pub mod nonce;
+pub mod siws;
+pub mod api_keys;
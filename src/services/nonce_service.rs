@@ -0,0 +1,35 @@
+use std::time::Duration;
+use tracing::{info, error};
+
+use crate::services::vault::VaultService;
+
+/// Periodically returns durable nonce reservations that never got a
+/// submitted transaction back to the pool.
+#[derive(Clone)]
+pub struct NonceReaper {
+    vault_service: VaultService,
+    reservation_ttl: Duration,
+}
+
+impl NonceReaper {
+    pub fn new(vault_service: VaultService, reservation_ttl: Duration) -> Self {
+        Self {
+            vault_service,
+            reservation_ttl,
+        }
+    }
+
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match self.vault_service.release_stale_nonces(self.reservation_ttl.as_secs() as i64).await {
+                Ok(0) => {}
+                Ok(released) => info!("Released {} stale nonce reservations", released),
+                Err(e) => error!("Releasing stale nonce reservations failed: {:?}", e),
+            }
+        }
+    }
+}
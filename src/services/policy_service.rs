@@ -0,0 +1,302 @@
+use anchor_client::solana_sdk::{
+    compute_budget,
+    pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+use serde::Serialize;
+
+use crate::services::transaction::durable_nonce_account;
+use crate::utils::anchor_client::VAULT_INSTRUCTIONS;
+use crate::utils::idl::instruction_discriminator;
+
+/// Instructions a submitted transaction may carry besides vault ones, by
+/// program and leading instruction data byte: setting the compute unit limit
+/// and price, and creating an associated token account if it is missing. The
+/// token program isn't here; the vault program moves tokens itself.
+const HELPER_INSTRUCTIONS: &[(Pubkey, u8)] = &[
+    (compute_budget::ID, 2),
+    (compute_budget::ID, 3),
+    (spl_associated_token_account::ID, 1),
+];
+
+/// Why a submitted transaction was refused. `instruction_index` and
+/// `program_id` point at the offending instruction when there is one.
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[error("Transaction rejected by policy: {reason}")]
+pub struct PolicyViolation {
+    pub reason: String,
+    pub instruction_index: Option<usize>,
+    pub program_id: Option<String>,
+}
+
+impl PolicyViolation {
+    fn transaction(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            instruction_index: None,
+            program_id: None,
+        }
+    }
+
+    fn instruction(index: usize, program_id: &Pubkey, reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            instruction_index: Some(index),
+            program_id: Some(program_id.to_string()),
+        }
+    }
+}
+
+/// Decides which client-signed transactions the backend is willing to relay,
+/// so `/transactions/submit` can't be used to broadcast arbitrary transactions.
+#[derive(Clone)]
+pub struct TransactionPolicy {
+    program_id: Pubkey,
+    admin: Pubkey,
+    allowed_discriminators: Vec<[u8; 8]>,
+}
+
+impl TransactionPolicy {
+    pub fn new(program_id: Pubkey, admin: Pubkey) -> Self {
+        Self {
+            program_id,
+            admin,
+            allowed_discriminators: VAULT_INSTRUCTIONS
+                .iter()
+                .map(|name| instruction_discriminator(name))
+                .collect(),
+        }
+    }
+
+    /// Accepts a transaction only if it is well formed and fully signed, every
+    /// instruction is a vault instruction or an allowed helper instruction
+    /// (after an optional leading nonce advance), at least
+    /// one vault instruction is present, and the fee payer is either the
+    /// admin key or a signer of one of the vault instructions.
+    pub fn check(&self, tx: &VersionedTransaction) -> Result<(), PolicyViolation> {
+        tx.sanitize()
+            .map_err(|e| PolicyViolation::transaction(format!("Malformed transaction: {}", e)))?;
+
+        if tx.verify_with_results().iter().any(|valid| !valid) {
+            return Err(PolicyViolation::transaction("Signature verification failed"));
+        }
+
+        let message = &tx.message;
+        // Program IDs and signers are always static keys, even in v0 messages
+        let keys = message.static_account_keys();
+        let num_signers = message.header().num_required_signatures as usize;
+        let fee_payer = keys[0];
+        let mut has_vault_instruction = false;
+        let mut vault_signers = Vec::new();
+
+        // A durable transaction starts with `advance_nonce_account`, which is
+        // the only system program instruction allowed
+        let skip = usize::from(durable_nonce_account(message).is_some());
+
+        for (index, instruction) in message.instructions().iter().enumerate().skip(skip) {
+            let program_id = keys[instruction.program_id_index as usize];
+
+            if program_id == self.program_id {
+                let known = instruction.data
+                    .get(..8)
+                    .map(|d| self.allowed_discriminators.iter().any(|a| a == d))
+                    .unwrap_or(false);
+
+                if !known {
+                    return Err(PolicyViolation::instruction(
+                        index,
+                        &program_id,
+                        "Unknown vault program instruction",
+                    ));
+                }
+
+                has_vault_instruction = true;
+                vault_signers.extend(
+                    instruction.accounts
+                        .iter()
+                        .map(|&i| i as usize)
+                        .filter(|&i| i < num_signers)
+                        .map(|i| keys[i]),
+                );
+            } else if !HELPER_INSTRUCTIONS.iter().any(|(program, _)| *program == program_id) {
+                return Err(PolicyViolation::instruction(
+                    index,
+                    &program_id,
+                    "Program is not allowed in relayed transactions",
+                ));
+            } else if !instruction.data.first().is_some_and(|&tag| HELPER_INSTRUCTIONS.contains(&(program_id, tag))) {
+                return Err(PolicyViolation::instruction(
+                    index,
+                    &program_id,
+                    "Instruction is not allowed in relayed transactions",
+                ));
+            }
+        }
+
+        if !has_vault_instruction {
+            return Err(PolicyViolation::transaction("Transaction contains no vault program instruction"));
+        }
+
+        if fee_payer != self.admin && !vault_signers.contains(&fee_payer) {
+            return Err(PolicyViolation::transaction(format!(
+                "Fee payer {} is neither the service key nor a signer of a vault instruction",
+                fee_payer,
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Rejects a transaction `signer` did not sign.
+    pub fn check_signed_by(&self, tx: &VersionedTransaction, signer: &Pubkey) -> Result<(), PolicyViolation> {
+        let num_signers = tx.message.header().num_required_signatures as usize;
+        let signed = tx.message.static_account_keys()
+            .iter()
+            .take(num_signers)
+            .any(|key| key == signer);
+
+        if !signed {
+            return Err(PolicyViolation::transaction(format!("Transaction is not signed by {}", signer)));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_client::solana_sdk::{
+        compute_budget::ComputeBudgetInstruction,
+        instruction::{AccountMeta, Instruction},
+        message::{Message, VersionedMessage},
+        signature::{Keypair, Signer},
+        system_instruction,
+    };
+    use spl_associated_token_account::instruction::{
+        create_associated_token_account,
+        create_associated_token_account_idempotent,
+    };
+
+    fn vault_instruction(program_id: Pubkey, name: &str, owner: Pubkey) -> Instruction {
+        let mut data = instruction_discriminator(name).to_vec();
+        data.extend_from_slice(&100u64.to_le_bytes());
+        Instruction::new_with_bytes(program_id, &data, vec![AccountMeta::new(owner, true)])
+    }
+
+    fn signed(instructions: &[Instruction], payer: &Keypair, signers: &[&Keypair]) -> VersionedTransaction {
+        let message = Message::new(instructions, Some(&payer.pubkey()));
+        VersionedTransaction::try_new(VersionedMessage::Legacy(message), signers).unwrap()
+    }
+
+    #[test]
+    fn checks_each_instruction_against_the_allow_list() {
+        let program_id = Pubkey::new_unique();
+        let owner = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let policy = TransactionPolicy::new(program_id, Pubkey::new_unique());
+
+        let deposit = vault_instruction(program_id, "deposit", owner.pubkey());
+        let create_ata = create_associated_token_account_idempotent(
+            &owner.pubkey(),
+            &owner.pubkey(),
+            &mint,
+            &anchor_spl::token::ID,
+        );
+        let token_transfer = Instruction::new_with_bytes(
+            anchor_spl::token::ID,
+            &[3, 1, 0, 0, 0, 0, 0, 0, 0],
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(owner.pubkey(), true),
+            ],
+        );
+
+        // Expected: `Ok(())`, or the index of the rejected instruction
+        let cases: Vec<(&str, Vec<Instruction>, Result<(), Option<usize>>)> = vec![
+            ("vault instruction alone", vec![deposit.clone()], Ok(())),
+            (
+                "compute budget",
+                vec![
+                    ComputeBudgetInstruction::set_compute_unit_limit(200_000),
+                    ComputeBudgetInstruction::set_compute_unit_price(1_000),
+                    deposit.clone(),
+                ],
+                Ok(()),
+            ),
+            ("idempotent token account creation", vec![create_ata, deposit.clone()], Ok(())),
+            (
+                "heap frame request",
+                vec![ComputeBudgetInstruction::request_heap_frame(64 * 1024), deposit.clone()],
+                Err(Some(0)),
+            ),
+            (
+                "plain token account creation",
+                vec![
+                    deposit.clone(),
+                    create_associated_token_account(&owner.pubkey(), &owner.pubkey(), &mint, &anchor_spl::token::ID),
+                ],
+                Err(Some(1)),
+            ),
+            ("token transfer", vec![deposit.clone(), token_transfer], Err(Some(1))),
+            (
+                "system transfer",
+                vec![
+                    deposit.clone(),
+                    system_instruction::transfer(&owner.pubkey(), &Pubkey::new_unique(), 1),
+                ],
+                Err(Some(1)),
+            ),
+            (
+                "unknown vault instruction",
+                vec![vault_instruction(program_id, "drain", owner.pubkey())],
+                Err(Some(0)),
+            ),
+            (
+                "no vault instruction",
+                vec![ComputeBudgetInstruction::set_compute_unit_price(1_000)],
+                Err(None),
+            ),
+        ];
+
+        for (name, instructions, expected) in cases {
+            let tx = signed(&instructions, &owner, &[&owner]);
+            let result = policy.check(&tx).map_err(|violation| violation.instruction_index);
+            assert_eq!(result, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn fee_payer_must_be_the_admin_or_a_vault_signer() {
+        let program_id = Pubkey::new_unique();
+        let admin = Keypair::new();
+        let owner = Keypair::new();
+        let stranger = Keypair::new();
+        let policy = TransactionPolicy::new(program_id, admin.pubkey());
+        let deposit = vault_instruction(program_id, "deposit", owner.pubkey());
+
+        let cases: [(&str, &Keypair, &[&Keypair], bool); 3] = [
+            ("owner pays", &owner, &[&owner], true),
+            ("admin pays", &admin, &[&admin, &owner], true),
+            ("someone else pays", &stranger, &[&stranger, &owner], false),
+        ];
+
+        for (name, payer, signers, allowed) in cases {
+            let tx = signed(&[deposit.clone()], payer, signers);
+            assert_eq!(policy.check(&tx).is_ok(), allowed, "{}", name);
+        }
+    }
+
+    #[test]
+    fn rejects_tampered_signatures() {
+        let program_id = Pubkey::new_unique();
+        let owner = Keypair::new();
+        let policy = TransactionPolicy::new(program_id, Pubkey::new_unique());
+
+        let mut tx = signed(&[vault_instruction(program_id, "deposit", owner.pubkey())], &owner, &[&owner]);
+        tx.signatures[0] = Keypair::new().sign_message(b"something else");
+
+        assert!(policy.check(&tx).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+use anyhow::Result;
+use tracing::{info, warn, error};
+
+use crate::database::DatabasePool;
+use crate::services::vault::VaultService;
+use crate::models::{
+    database::Vault,
+    onchain::OnchainVaultState,
+    responses::{BalanceDiff, DriftReport, VaultDrift},
+};
+
+/// Compares cached `vaults` rows against the on-chain vault accounts and
+/// rewrites the database when they disagree.
+#[derive(Clone)]
+pub struct Reconciler {
+    db_pool: DatabasePool,
+    vault_service: VaultService,
+}
+
+impl Reconciler {
+    pub fn new(db_pool: DatabasePool, vault_service: VaultService) -> Self {
+        Self {
+            db_pool,
+            vault_service,
+        }
+    }
+
+    /// Runs `reconcile_once` forever, sleeping `interval` between passes.
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match self.reconcile_once().await {
+                Ok(report) => info!(
+                    "Reconciled {} vaults, {} drifted",
+                    report.vaults_checked,
+                    report.drifted.len(),
+                ),
+                Err(e) => error!("Vault reconciliation failed: {:?}", e),
+            }
+        }
+    }
+
+    /// Reports drift without touching the database.
+    pub async fn drift_report(&self) -> Result<DriftReport> {
+        self.check_all(false).await
+    }
+
+    /// Reports drift and overwrites drifted rows with on-chain values.
+    pub async fn reconcile_once(&self) -> Result<DriftReport> {
+        self.check_all(true).await
+    }
+
+    async fn check_all(&self, correct: bool) -> Result<DriftReport> {
+        let vaults = self.db_pool.list_vaults().await?;
+        let mut drifted = Vec::new();
+
+        for vault in &vaults {
+            let drift = match self.vault_service.get_onchain_vault(&vault.owner).await {
+                Ok(onchain) => {
+                    let mut drift = diff_vault(vault, &onchain);
+                    if let Some(difference) = drift.token_account_drift {
+                        warn!(
+                            "Token account of vault {} differs from its total balance by {}",
+                            vault.owner,
+                            difference,
+                        );
+                    }
+                    if correct && !drift.fields.is_empty() {
+                        // One failed write shouldn't stop the rest of the pass
+                        if let Err(e) = self.correct_vault(vault, &onchain, &drift).await {
+                            error!("Could not correct drift for vault {}: {:?}", vault.owner, e);
+                            drift.error = Some(format!("Correction failed: {}", e));
+                        }
+                    }
+                    drift
+                }
+                Err(e) => {
+                    warn!("Could not load on-chain vault for {}: {:?}", vault.owner, e);
+                    VaultDrift {
+                        owner: vault.owner.clone(),
+                        vault_address: vault.vault_address.clone(),
+                        fields: BTreeMap::new(),
+                        token_account_balance: None,
+                        token_account_drift: None,
+                        error: Some(e.to_string()),
+                    }
+                }
+            };
+
+            if !drift.fields.is_empty() || drift.token_account_drift.is_some() || drift.error.is_some() {
+                drifted.push(drift);
+            }
+        }
+
+        Ok(DriftReport {
+            checked_at: chrono::Utc::now(),
+            vaults_checked: vaults.len(),
+            corrected: correct,
+            drifted,
+        })
+    }
+
+    async fn correct_vault(
+        &self,
+        vault: &Vault,
+        onchain: &OnchainVaultState,
+        drift: &VaultDrift,
+    ) -> Result<()> {
+        let corrected = Vault {
+            total_balance: onchain.total_balance as i64,
+            locked_balance: onchain.locked_balance as i64,
+            available_balance: onchain.available_balance as i64,
+            total_deposited: onchain.total_deposited as i64,
+            total_withdrawn: onchain.total_withdrawn as i64,
+            ..vault.clone()
+        };
+
+        self.db_pool.set_vault_balances(&corrected).await?;
+
+        self.db_pool.store_vault_event(&vault.owner, "reconciled", &serde_json::json!({
+            "diff": drift.fields,
+            "token_account_balance": drift.token_account_balance,
+        })).await?;
+
+        info!("Corrected drift for vault {}: {:?}", vault.owner, drift.fields.keys());
+
+        Ok(())
+    }
+}
+
+fn diff_vault(vault: &Vault, onchain: &OnchainVaultState) -> VaultDrift {
+    let pairs = [
+        ("total_balance", vault.total_balance, onchain.total_balance),
+        ("locked_balance", vault.locked_balance, onchain.locked_balance),
+        ("available_balance", vault.available_balance, onchain.available_balance),
+        ("total_deposited", vault.total_deposited, onchain.total_deposited),
+        ("total_withdrawn", vault.total_withdrawn, onchain.total_withdrawn),
+    ];
+
+    let fields = pairs
+        .into_iter()
+        .filter(|(_, database, onchain)| *database != *onchain as i64)
+        .map(|(name, database, onchain)| {
+            (name.to_string(), BalanceDiff { database, onchain: onchain as i64 })
+        })
+        .collect();
+
+    let token_account_drift = onchain.token_account_balance as i64 - onchain.total_balance as i64;
+
+    VaultDrift {
+        owner: vault.owner.clone(),
+        vault_address: vault.vault_address.clone(),
+        fields,
+        token_account_balance: Some(onchain.token_account_balance),
+        token_account_drift: (token_account_drift != 0).then_some(token_account_drift),
+        error: None,
+    }
+}
@@ -1,79 +1,384 @@
+use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use solana_client::{
-    rpc_client::RpcClient,
-    rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig},
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{
+        RpcAccountInfoConfig, RpcContextConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
+        RpcSimulateTransactionConfig, RpcTransactionConfig,
+    },
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_request::RpcRequest,
+    rpc_response::{
+        Response, RpcBlockhash, RpcConfirmedTransactionStatusWithSignature,
+        RpcSimulateTransactionResult,
+    },
 };
 use solana_sdk::{
     commitment_config::CommitmentConfig,
+    hash::Hash,
+    nonce,
+    pubkey::Pubkey,
     signature::Signature,
-    transaction::Transaction,
+    message::VersionedMessage,
+    transaction::VersionedTransaction,
+};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer,
+    TransactionStatus as SignatureStatus,
+    UiTransactionEncoding,
 };
 use anyhow::{Result, Context};
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+use serde_json::json;
+use tokio::sync::RwLock;
+
+use crate::models::requests::PriorityLevel;
+use crate::services::rpc_pool::{EndpointStats, RpcPool};
+use crate::utils::idl::Idl;
+
+/// How long prioritization fee percentiles are reused before re-querying.
+const FEE_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Account sets kept in the fee cache. Callers choose the sets, so the
+/// oldest entries are evicted past this.
+const FEE_CACHE_MAX_ENTRIES: usize = 1024;
+
+/// Most accounts `getRecentPrioritizationFees` accepts.
+pub const MAX_FEE_ACCOUNTS: usize = 128;
 
 #[derive(Clone)]
 pub struct RpcService {
-    rpc_client: Arc<Mutex<RpcClient>>,
+    pool: RpcPool,
     commitment: CommitmentConfig,
+    fee_cache: Arc<RwLock<HashMap<Vec<Pubkey>, (Instant, FeeEstimate)>>>,
 }
 
 impl RpcService {
-    pub fn new(rpc_url: &str) -> Result<Self> {
-        let rpc_client = RpcClient::new(rpc_url.to_string());
+    pub fn new(rpc_urls: &[String]) -> Result<Self> {
+        let commitment = CommitmentConfig::confirmed();
         
         Ok(Self {
-            rpc_client: Arc::new(Mutex::new(rpc_client)),
-            commitment: CommitmentConfig::confirmed(),
+            pool: RpcPool::new(rpc_urls, commitment)?,
+            commitment,
+            fee_cache: Arc::new(RwLock::new(HashMap::new())),
         })
     }
     
+    pub fn pool(&self) -> &RpcPool {
+        &self.pool
+    }
+    
+    pub async fn endpoint_stats(&self) -> Vec<EndpointStats> {
+        self.pool.stats().await
+    }
+    
+    /// Priority fee percentiles (micro-lamports per CU) over the recent slots
+    /// returned by `getRecentPrioritizationFees` for the given writable accounts.
+    pub async fn estimate_priority_fees(
+        &self,
+        writable_accounts: &[Pubkey],
+    ) -> Result<FeeEstimate> {
+        let mut key = writable_accounts.to_vec();
+        key.sort();
+        key.dedup();
+        
+        if key.len() > MAX_FEE_ACCOUNTS {
+            anyhow::bail!("At most {} accounts can be used for a fee estimate", MAX_FEE_ACCOUNTS);
+        }
+        
+        if let Some((fetched_at, estimate)) = self.fee_cache.read().await.get(&key) {
+            if fetched_at.elapsed() < FEE_CACHE_TTL {
+                return Ok(estimate.clone());
+            }
+        }
+        
+        let fees = self.pool
+            .call("getRecentPrioritizationFees", |client| {
+                let key = &key;
+                async move { client.get_recent_prioritization_fees(key).await }
+            })
+            .await
+            .context("Failed to fetch recent prioritization fees")?;
+        
+        let mut samples: Vec<u64> = fees.iter().map(|f| f.prioritization_fee).collect();
+        samples.sort_unstable();
+        
+        let estimate = FeeEstimate {
+            low: percentile(&samples, 25),
+            medium: percentile(&samples, 50),
+            high: percentile(&samples, 75),
+            max: percentile(&samples, 95),
+            slots_sampled: samples.len(),
+            accounts: key.iter().map(|k| k.to_string()).collect(),
+            updated_at: chrono::Utc::now(),
+        };
+        
+        let mut cache = self.fee_cache.write().await;
+        if cache.len() >= FEE_CACHE_MAX_ENTRIES {
+            cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < FEE_CACHE_TTL);
+        }
+        if cache.len() >= FEE_CACHE_MAX_ENTRIES {
+            let oldest = cache.iter()
+                .min_by_key(|(_, (fetched_at, _))| *fetched_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(key, (Instant::now(), estimate.clone()));
+        
+        Ok(estimate)
+    }
+    
     pub async fn send_transaction(
         &self,
-        transaction: &Transaction,
+        transaction: &VersionedTransaction,
     ) -> Result<Signature> {
-        let client = self.rpc_client.lock().await;
-        
         let config = RpcSendTransactionConfig {
             skip_preflight: false,
             preflight_commitment: Some(self.commitment.commitment),
             encoding: None,
-            max_retries: None,
+            // Retries are handled by `TransactionSender`
+            max_retries: Some(0),
             min_context_slot: None,
         };
         
-        let signature = client.send_transaction_with_config(transaction, config)
+        let signature = self.pool
+            .call("sendTransaction", |client| async move {
+                client.send_transaction_with_config(transaction, config).await
+            })
+            .await
             .context("Failed to send transaction")?;
         
         Ok(signature)
     }
     
+    /// Resends a transaction that already passed preflight on its first send.
+    pub async fn rebroadcast_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<Signature> {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            preflight_commitment: None,
+            encoding: None,
+            max_retries: Some(0),
+            min_context_slot: None,
+        };
+        
+        // Reaching two providers at once improves the odds of landing
+        let signature = self.pool
+            .hedged("sendTransaction", |client| async move {
+                client.send_transaction_with_config(transaction, config).await
+            })
+            .await
+            .context("Failed to rebroadcast transaction")?;
+        
+        Ok(signature)
+    }
+    
     pub async fn confirm_transaction(
         &self,
         signature: &Signature,
     ) -> Result<()> {
-        let client = self.rpc_client.lock().await;
+        let commitment = self.commitment;
         
-        client.confirm_transaction_with_commitment(
-            signature,
-            &self.commitment,
-        )?;
+        self.pool
+            .call("confirmTransaction", |client| async move {
+                client.confirm_transaction_with_commitment(signature, commitment).await
+            })
+            .await?;
         
         Ok(())
     }
     
-    pub async fn get_fee_for_transaction(
+    pub async fn get_signature_statuses(
         &self,
-        transaction: &Transaction,
-    ) -> Result<u64> {
-        let client = self.rpc_client.lock().await;
+        signatures: &[Signature],
+    ) -> Result<Vec<Option<SignatureStatus>>> {
+        let response = self.pool
+            .hedged("getSignatureStatuses", |client| async move {
+                client.get_signature_statuses(signatures).await
+            })
+            .await
+            .context("Failed to fetch signature statuses")?;
+        
+        Ok(response.value)
+    }
+    
+    /// Like `get_signature_statuses`, but also searches ledger history for
+    /// transactions that have already left the status cache.
+    pub async fn get_signature_statuses_with_history(
+        &self,
+        signatures: &[Signature],
+    ) -> Result<Vec<Option<SignatureStatus>>> {
+        let response = self.pool
+            .call("getSignatureStatuses", |client| async move {
+                client.get_signature_statuses_with_history(signatures).await
+            })
+            .await
+            .context("Failed to fetch signature statuses")?;
+        
+        Ok(response.value)
+    }
+    
+    pub async fn is_blockhash_valid(&self, blockhash: &Hash) -> Result<bool> {
+        let commitment = self.commitment;
+        
+        let config = RpcContextConfig {
+            commitment: Some(commitment),
+            min_context_slot: None,
+        };
+        
+        // Sent raw for the response context, see `RpcPool::hedged`
+        let valid = self.pool
+            .hedged("isBlockhashValid", |client| async move {
+                client
+                    .send::<Response<bool>>(
+                        RpcRequest::IsBlockhashValid,
+                        json!([blockhash.to_string(), config]),
+                    )
+                    .await
+            })
+            .await?;
+        
+        Ok(valid.value)
+    }
+    
+    /// Current block height, to compare with a blockhash's `last_valid_block_height`.
+    pub async fn get_block_height(&self) -> Result<u64> {
+        let commitment = self.commitment;
+        
+        let height = self.pool
+            .call("getBlockHeight", |client| async move {
+                client.get_block_height_with_commitment(commitment).await
+            })
+            .await
+            .context("Failed to fetch block height")?;
+        
+        Ok(height)
+    }
+    
+    pub async fn get_latest_blockhash(&self) -> Result<Hash> {
+        let (blockhash, _) = self.get_latest_blockhash_with_height().await?;
+        
+        Ok(blockhash)
+    }
+    
+    /// Current value of a durable nonce account, used in place of a blockhash.
+    pub async fn get_nonce_blockhash(&self, nonce_account: &Pubkey) -> Result<Hash> {
+        let data = self.get_account_data(nonce_account).await
+            .context("Nonce account not found on chain")?;
+        let versions: nonce::state::Versions = bincode::deserialize(&data)
+            .context("Account is not a nonce account")?;
+        
+        match versions.state() {
+            nonce::State::Initialized(state) => Ok(state.blockhash()),
+            nonce::State::Uninitialized => anyhow::bail!("Nonce account {} is not initialized", nonce_account),
+        }
+    }
+    
+    pub async fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        let lamports = self.pool
+            .call("getMinimumBalanceForRentExemption", |client| async move {
+                client.get_minimum_balance_for_rent_exemption(data_len).await
+            })
+            .await
+            .context("Failed to fetch rent exemption minimum")?;
+        
+        Ok(lamports)
+    }
+    
+    pub async fn get_slot(&self) -> Result<u64> {
+        let commitment = self.commitment;
+        
+        let slot = self.pool
+            .call("getSlot", |client| async move {
+                client.get_slot_with_commitment(commitment).await
+            })
+            .await
+            .context("Failed to fetch slot")?;
+        
+        Ok(slot)
+    }
+    
+    /// Waits until the cluster has moved past `slot`, giving up after `timeout`.
+    pub async fn wait_past_slot(&self, slot: u64, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        
+        while self.get_slot().await? <= slot {
+            if Instant::now() >= deadline {
+                anyhow::bail!("Cluster did not pass slot {} within {:?}", slot, timeout);
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+        
+        Ok(())
+    }
+    
+    /// Latest blockhash together with the last block height at which a
+    /// transaction using it can still be included.
+    pub async fn get_latest_blockhash_with_height(&self) -> Result<(Hash, u64)> {
+        let config = RpcContextConfig {
+            commitment: Some(self.commitment),
+            min_context_slot: None,
+        };
+        
+        let response = self.pool
+            .hedged("getLatestBlockhash", |client| async move {
+                client
+                    .send::<Response<RpcBlockhash>>(RpcRequest::GetLatestBlockhash, json!([config]))
+                    .await
+            })
+            .await
+            .context("Failed to fetch latest blockhash")?;
+        
+        let blockhash = Hash::from_str(&response.value.blockhash)
+            .context("Invalid blockhash returned by RPC")?;
+        
+        Ok((blockhash, response.value.last_valid_block_height))
+    }
+    
+    /// Simulates without signature verification so a transaction can be
+    /// sized before every required signer has signed it.
+    pub async fn simulate_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<RpcSimulateTransactionResult> {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(self.commitment),
+            ..RpcSimulateTransactionConfig::default()
+        };
         
-        let recent_blockhash = client.get_latest_blockhash()?;
-        let fee_calculator = client.get_fee_calculator_for_blockhash(&recent_blockhash)?;
+        let response = self.pool
+            .call("simulateTransaction", |client| {
+                let config = config.clone();
+                async move { client.simulate_transaction_with_config(transaction, config).await }
+            })
+            .await
+            .context("Failed to simulate transaction")?;
         
-        let fee = fee_calculator
-            .map(|calc| calc.lamports_per_signature * transaction.signatures.len() as u64)
-            .unwrap_or(5000); // Default fee
+        Ok(response.value)
+    }
+    
+    /// Total fee for the message, including prioritization fees.
+    pub async fn get_fee_for_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<u64> {
+        let fee = self.pool
+            .call("getFeeForMessage", |client| async move {
+                match &transaction.message {
+                    VersionedMessage::Legacy(message) => client.get_fee_for_message(message).await,
+                    VersionedMessage::V0(message) => client.get_fee_for_message(message).await,
+                }
+            })
+            .await
+            .context("Failed to fetch fee for message")?;
         
         Ok(fee)
     }
@@ -82,15 +387,17 @@ impl RpcService {
         &self,
         signature: &Signature,
     ) -> Result<TransactionStatus> {
-        let client = self.rpc_client.lock().await;
-        
         let config = RpcTransactionConfig {
             encoding: None,
             commitment: Some(self.commitment),
             max_supported_transaction_version: Some(0),
         };
         
-        let response = client.get_transaction_with_config(signature, config)?;
+        let response = self.pool
+            .call("getTransaction", |client| async move {
+                client.get_transaction_with_config(signature, config).await
+            })
+            .await?;
         
         Ok(TransactionStatus {
             signature: signature.to_string(),
@@ -110,10 +417,142 @@ impl RpcService {
         &self,
         pubkey: &solana_sdk::pubkey::Pubkey,
     ) -> Result<Vec<u8>> {
-        let client = self.rpc_client.lock().await;
-        let account = client.get_account(pubkey)?;
+        let commitment = self.commitment;
+        
+        let response = self.pool
+            .hedged("getAccountInfo", |client| async move {
+                client.get_account_with_commitment(pubkey, commitment).await
+            })
+            .await?;
+        
+        let account = response.value
+            .ok_or(AccountNotFound(*pubkey))?;
+        
         Ok(account.data)
     }
+    
+    /// Returns up to `limit` signatures involving `address`, newest first,
+    /// strictly older than `before` and newer than `until`.
+    pub async fn get_signatures_for_address(
+        &self,
+        address: &Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+    ) -> Result<Vec<RpcConfirmedTransactionStatusWithSignature>> {
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before,
+            until,
+            limit: Some(limit),
+            commitment: Some(CommitmentConfig::finalized()),
+        };
+        
+        let signatures = self.pool
+            .call("getSignaturesForAddress", |client| {
+                let config = GetConfirmedSignaturesForAddress2Config {
+                    before: config.before,
+                    until: config.until,
+                    limit: config.limit,
+                    commitment: config.commitment,
+                };
+                async move { client.get_signatures_for_address_with_config(address, config).await }
+            })
+            .await
+            .context("Failed to fetch signatures for address")?;
+        
+        Ok(signatures)
+    }
+    
+    pub async fn get_transaction_logs(
+        &self,
+        signature: &Signature,
+    ) -> Result<TransactionLogs> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Json),
+            commitment: Some(CommitmentConfig::finalized()),
+            max_supported_transaction_version: Some(0),
+        };
+        
+        let response = self.pool
+            .call("getTransaction", |client| async move {
+                client.get_transaction_with_config(signature, config).await
+            })
+            .await?;
+        let meta = response.transaction.meta
+            .context("Transaction has no status metadata")?;
+        
+        let logs = match meta.log_messages {
+            OptionSerializer::Some(logs) => logs,
+            _ => Vec::new(),
+        };
+        
+        Ok(TransactionLogs {
+            slot: response.slot,
+            block_time: response.block_time,
+            fee: meta.fee,
+            logs,
+            error: meta.err.map(|e| format!("{:?}", e)),
+        })
+    }
+    
+    /// Returns every account owned by `program_id` whose data starts with
+    /// the given Anchor account discriminator.
+    pub async fn get_program_accounts_with_discriminator(
+        &self,
+        program_id: &Pubkey,
+        discriminator: [u8; 8],
+    ) -> Result<Vec<(Pubkey, Vec<u8>)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+                0,
+                discriminator.to_vec(),
+            ))]),
+            account_config: RpcAccountInfoConfig {
+                commitment: Some(self.commitment),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        
+        let accounts = self.pool
+            .call("getProgramAccounts", |client| {
+                let config = config.clone();
+                async move { client.get_program_accounts_with_config(program_id, config).await }
+            })
+            .await
+            .context("Failed to fetch program accounts")?;
+        
+        Ok(accounts
+            .into_iter()
+            .map(|(pubkey, account)| (pubkey, account.data))
+            .collect())
+    }
+    
+    pub async fn get_token_account_mint(&self, pubkey: &Pubkey) -> Result<Pubkey> {
+        let account = self.pool
+            .call("getTokenAccount", |client| async move {
+                client.get_token_account(pubkey).await
+            })
+            .await?
+            .context("Token account not found")?;
+        
+        Pubkey::from_str(&account.mint).context("Invalid mint in token account")
+    }
+    
+    pub async fn get_token_account_balance(
+        &self,
+        pubkey: &solana_sdk::pubkey::Pubkey,
+    ) -> Result<u64> {
+        let balance = self.pool
+            .call("getTokenAccountBalance", |client| async move {
+                client.get_token_account_balance(pubkey).await
+            })
+            .await?;
+        
+        balance.amount
+            .parse()
+            .context("Invalid token amount returned by RPC")
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -124,4 +563,119 @@ pub struct TransactionStatus {
     pub block_time: Option<i64>,
     pub confirmation_status: Option<String>,
     pub error: Option<String>,
-}
\ No newline at end of file
+}
+
+/// Status metadata and logs of a finalized transaction.
+#[derive(Debug, Clone)]
+pub struct TransactionLogs {
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub fee: u64,
+    pub logs: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    pub low: u64,
+    pub medium: u64,
+    pub high: u64,
+    pub max: u64,
+    pub slots_sampled: usize,
+    pub accounts: Vec<String>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl FeeEstimate {
+    pub fn for_level(&self, level: PriorityLevel) -> u64 {
+        match level {
+            PriorityLevel::Low => self.low,
+            PriorityLevel::Medium => self.medium,
+            PriorityLevel::High => self.high,
+            PriorityLevel::Max => self.max,
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice; 0 when there are no samples.
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    
+    let rank = (pct * sorted.len() + 99) / 100;
+    sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgramError {
+    pub code: u32,
+    pub name: Option<String>,
+    pub message: Option<String>,
+}
+
+/// The account doesn't exist at the service's commitment.
+#[derive(Debug, thiserror::Error)]
+#[error("Account {0} not found")]
+pub struct AccountNotFound(pub Pubkey);
+
+/// A transaction that failed simulation, with the program error decoded
+/// from the IDL where possible.
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[error("Transaction simulation failed: {error}")]
+pub struct SimulationFailure {
+    pub error: String,
+    pub program_error: Option<ProgramError>,
+    pub logs: Vec<String>,
+    pub units_consumed: Option<u64>,
+}
+
+impl SimulationFailure {
+    pub fn new(error: String, logs: Vec<String>, units_consumed: Option<u64>, idl: &Idl) -> Self {
+        let program_error = parse_anchor_error(&logs)
+            .or_else(|| parse_custom_code(&error).map(|code| ProgramError {
+                code,
+                name: None,
+                message: None,
+            }))
+            .map(|mut program_error| {
+                if let Some(declared) = idl.error(program_error.code) {
+                    program_error.name = Some(declared.name.clone());
+                    program_error.message = declared.msg.clone().or(program_error.message);
+                }
+                program_error
+            });
+        
+        Self {
+            error,
+            program_error,
+            logs,
+            units_consumed,
+        }
+    }
+}
+
+/// Parses Anchor's `AnchorError ... Error Code: X. Error Number: N. Error Message: M.` log line.
+fn parse_anchor_error(logs: &[String]) -> Option<ProgramError> {
+    let line = logs.iter().find(|l| l.contains("AnchorError"))?;
+    
+    let field = |label: &str| -> Option<String> {
+        let start = line.find(label)? + label.len();
+        let rest = &line[start..];
+        let end = rest.find(". ").unwrap_or(rest.len());
+        Some(rest[..end].trim_end_matches('.').trim().to_string())
+    };
+    
+    Some(ProgramError {
+        code: field("Error Number: ")?.parse().ok()?,
+        name: field("Error Code: "),
+        message: field("Error Message: "),
+    })
+}
+
+/// Extracts `N` from `InstructionError(_, Custom(N))`.
+fn parse_custom_code(error: &str) -> Option<u32> {
+    let start = error.find("Custom(")? + "Custom(".len();
+    let end = error[start..].find(')')? + start;
+    error[start..end].parse().ok()
+}
@@ -0,0 +1,243 @@
+use std::future::Future;
+use std::time::Duration;
+use anchor_client::solana_sdk::{signature::Signature, transaction::VersionedTransaction};
+use anyhow::{Result, bail};
+use solana_transaction_status::{TransactionConfirmationStatus, TransactionStatus};
+use tracing::{info, warn};
+
+use crate::services::rpc::RpcService;
+use crate::services::transaction::durable_nonce_account;
+use crate::utils::anchor_client::SignedTransaction;
+
+#[derive(Debug, Clone)]
+pub struct SendConfig {
+    /// How often an unconfirmed transaction is rebroadcast.
+    pub rebroadcast_interval: Duration,
+    /// Total number of blockhashes a server-signed transaction may be built with.
+    pub max_attempts: u32,
+    /// Priority fee increase applied on every rebuild, in percent.
+    pub fee_bump_percent: u64,
+    /// Fee used for the first bump when the caller didn't set one (micro-lamports per CU).
+    pub min_priority_fee: u64,
+    /// Upper bound for bumped priority fees (micro-lamports per CU).
+    pub max_priority_fee: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum SendOutcome {
+    Confirmed { slot: u64 },
+    Failed(String),
+    Expired,
+}
+
+/// Broadcasts transactions and keeps rebroadcasting them until the cluster
+/// confirms them or their blockhash expires.
+#[derive(Clone)]
+pub struct TransactionSender {
+    rpc_service: RpcService,
+    config: SendConfig,
+}
+
+impl TransactionSender {
+    pub fn new(rpc_service: RpcService, config: SendConfig) -> Self {
+        Self {
+            rpc_service,
+            config,
+        }
+    }
+
+    /// Sends an already-signed transaction, rebroadcasting the same bytes
+    /// every `rebroadcast_interval` until it lands or can no longer land.
+    /// Only a failed first send is an error; after that the transaction may
+    /// land at any time, so the outcome is all the caller gets.
+    pub async fn send_until_settled(
+        &self,
+        tx: &VersionedTransaction,
+        last_valid_block_height: Option<u64>,
+    ) -> Result<(Signature, SendOutcome)> {
+        let signature = self.rpc_service.send_transaction(tx).await?;
+        let outcome = self.rebroadcast_until_settled(tx, signature, last_valid_block_height).await;
+
+        Ok((signature, outcome))
+    }
+
+    /// Rebroadcast loop for a transaction that has already been sent once.
+    /// `last_valid_block_height` is `None` when the caller doesn't know it,
+    /// e.g. for client-built transactions. RPC errors are logged and retried
+    /// on the next pass rather than given up on.
+    pub async fn rebroadcast_until_settled(
+        &self,
+        tx: &VersionedTransaction,
+        signature: Signature,
+        last_valid_block_height: Option<u64>,
+    ) -> SendOutcome {
+        let mut rebroadcasts = 0u32;
+
+        loop {
+            tokio::time::sleep(self.config.rebroadcast_interval).await;
+
+            let status = match self.rpc_service.get_signature_statuses(&[signature]).await {
+                Ok(statuses) => statuses.into_iter().next().flatten(),
+                Err(e) => {
+                    warn!("Failed to fetch status of {}: {:?}", signature, e);
+                    continue;
+                }
+            };
+
+            if let Some(status) = &status {
+                // Processed: wait until it confirms or its fork is dropped
+                if let Some(outcome) = settled_outcome(status) {
+                    return outcome;
+                }
+                continue;
+            }
+
+            match self.is_expired(tx, last_valid_block_height).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    // Only resend what the cluster hasn't seen yet
+                    rebroadcasts += 1;
+                    if let Err(e) = self.rpc_service.rebroadcast_transaction(tx).await {
+                        warn!("Rebroadcast #{} of {} failed: {:?}", rebroadcasts, signature, e);
+                    }
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to check expiry of {}: {:?}", signature, e);
+                    continue;
+                }
+            }
+
+            // It may have landed between the two reads. Only a transaction
+            // the ledger has no trace of is safe to rebuild.
+            let landed = match self.rpc_service.get_signature_statuses_with_history(&[signature]).await {
+                Ok(statuses) => statuses.into_iter().next().flatten(),
+                Err(e) => {
+                    warn!("Failed to fetch status history of {}: {:?}", signature, e);
+                    continue;
+                }
+            };
+
+            match landed {
+                None => {
+                    info!("Transaction {} expired after {} rebroadcasts", signature, rebroadcasts);
+                    return SendOutcome::Expired;
+                }
+                // Settle from the history: the recent status cache may already
+                // have dropped it, and then the lookup above never sees it again
+                Some(status) => {
+                    if let Some(outcome) = settled_outcome(&status) {
+                        return outcome;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Builds, signs and sends a server-signed transaction. When an attempt
+    /// expires, `build` is called again for a fresh blockhash with a bumped
+    /// priority fee, up to `max_attempts` times. `on_attempt` is invoked with
+    /// every transaction before it is broadcast, and `on_error` with its
+    /// signature if it couldn't be sent at all.
+    pub async fn send_with_rebuild<B, BFut, A, AFut, E, EFut>(
+        &self,
+        priority_fee: Option<u64>,
+        build: B,
+        on_attempt: A,
+        on_error: E,
+    ) -> Result<(VersionedTransaction, Signature)>
+    where
+        B: Fn(Option<u64>) -> BFut,
+        BFut: Future<Output = Result<SignedTransaction>>,
+        A: Fn(VersionedTransaction) -> AFut,
+        AFut: Future<Output = Result<()>>,
+        E: Fn(Signature, String) -> EFut,
+        EFut: Future<Output = Result<()>>,
+    {
+        let mut fee = priority_fee;
+
+        for attempt in 1..=self.config.max_attempts {
+            let SignedTransaction { transaction: tx, last_valid_block_height } = build(fee).await?;
+            on_attempt(tx.clone()).await?;
+
+            info!(
+                "Sending attempt {}/{} with blockhash {} and priority fee {:?}",
+                attempt,
+                self.config.max_attempts,
+                tx.message.recent_blockhash(),
+                fee,
+            );
+
+            let (signature, outcome) = match self.send_until_settled(&tx, Some(last_valid_block_height)).await {
+                Ok(result) => result,
+                Err(e) => {
+                    let signature = tx.signatures.first().copied().unwrap_or_default();
+                    if let Err(mark) = on_error(signature, e.to_string()).await {
+                        warn!("Failed to record failure of {}: {:?}", signature, mark);
+                    }
+                    return Err(e);
+                }
+            };
+
+            match outcome {
+                SendOutcome::Confirmed { slot } => {
+                    info!("Transaction {} confirmed at slot {} on attempt {}", signature, slot, attempt);
+                    return Ok((tx, signature));
+                }
+                SendOutcome::Failed(err) => {
+                    bail!("Transaction {} failed: {}", signature, err);
+                }
+                SendOutcome::Expired => {
+                    fee = Some(self.bump_fee(fee));
+                    warn!("Attempt {} ({}) expired, rebuilding with priority fee {:?}", attempt, signature, fee);
+                }
+            }
+        }
+
+        bail!("Transaction not confirmed after {} attempts", self.config.max_attempts)
+    }
+
+    /// A regular transaction expires once the confirmed block height passes
+    /// the last height its blockhash is valid for; a durable one once its
+    /// nonce account has been advanced past the nonce it was signed with.
+    /// The transaction landing advances the nonce too, so a `true` here must
+    /// be checked against the signature status before acting on it.
+    async fn is_expired(&self, tx: &VersionedTransaction, last_valid_block_height: Option<u64>) -> Result<bool> {
+        let recent_blockhash = tx.message.recent_blockhash();
+        
+        match (durable_nonce_account(&tx.message), last_valid_block_height) {
+            (Some(nonce_account), _) => {
+                let current = self.rpc_service.get_nonce_blockhash(&nonce_account).await?;
+                Ok(current != *recent_blockhash)
+            }
+            (None, Some(last_valid_block_height)) => {
+                Ok(self.rpc_service.get_block_height().await? > last_valid_block_height)
+            }
+            (None, None) => Ok(!self.rpc_service.is_blockhash_valid(recent_blockhash).await?),
+        }
+    }
+
+    fn bump_fee(&self, fee: Option<u64>) -> u64 {
+        let bumped = match fee {
+            Some(fee) if fee > 0 => fee.saturating_mul(100 + self.config.fee_bump_percent) / 100,
+            _ => self.config.min_priority_fee,
+        };
+
+        bumped.min(self.config.max_priority_fee)
+    }
+}
+
+/// `Failed` or `Confirmed` for a status that has settled, `None` while it is
+/// only processed.
+fn settled_outcome(status: &TransactionStatus) -> Option<SendOutcome> {
+    if let Some(err) = &status.err {
+        return Some(SendOutcome::Failed(format!("{:?}", err)));
+    }
+
+    match status.confirmation_status {
+        Some(TransactionConfirmationStatus::Confirmed) | Some(TransactionConfirmationStatus::Finalized) => {
+            Some(SendOutcome::Confirmed { slot: status.slot })
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,199 @@
+use std::str::FromStr;
+use std::time::Duration;
+use anchor_client::solana_sdk::{hash::Hash, pubkey::Pubkey, signature::Signature};
+use anyhow::Result;
+use solana_transaction_status::TransactionConfirmationStatus;
+use tracing::{info, warn, error, debug};
+
+use crate::database::DatabasePool;
+use crate::services::rpc::RpcService;
+use crate::models::database::TransactionLog;
+
+/// `getSignatureStatuses` accepts at most 256 signatures per call.
+const BATCH_SIZE: i64 = 256;
+
+/// Polls unsettled rows in `transaction_logs` and moves them through
+/// `pending → processed → confirmed → finalized`, or to `failed` / `expired`.
+#[derive(Clone)]
+pub struct TransactionTracker {
+    db_pool: DatabasePool,
+    rpc_service: RpcService,
+}
+
+impl TransactionTracker {
+    pub fn new(db_pool: DatabasePool, rpc_service: RpcService) -> Self {
+        Self {
+            db_pool,
+            rpc_service,
+        }
+    }
+
+    pub async fn run(self, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            if let Err(e) = self.poll_once().await {
+                error!("Transaction status poll failed: {:?}", e);
+            }
+        }
+    }
+
+    pub async fn poll_once(&self) -> Result<()> {
+        let logs = self.db_pool.list_unsettled_transactions(BATCH_SIZE).await?;
+        if logs.is_empty() {
+            return Ok(());
+        }
+
+        let mut tracked = Vec::with_capacity(logs.len());
+        for log in logs {
+            match Signature::from_str(&log.signature) {
+                Ok(signature) => tracked.push((signature, log)),
+                Err(_) => warn!("Ignoring malformed signature {} in transaction_logs", log.signature),
+            }
+        }
+
+        // With history, so rows that fell out of the status cache still settle
+        let signatures: Vec<Signature> = tracked.iter().map(|(sig, _)| *sig).collect();
+        let statuses = self.rpc_service.get_signature_statuses_with_history(&signatures).await?;
+
+        let polled: Vec<String> = tracked.iter().map(|(_, log)| log.signature.clone()).collect();
+
+        for ((signature, log), status) in tracked.into_iter().zip(statuses) {
+            let result = match status {
+                Some(status) => self.apply_status(&signature, &log, status).await,
+                None => self.check_expiry(&signature, &log).await,
+            };
+
+            if let Err(e) = result {
+                warn!("Failed to update status of {}: {:?}", log.signature, e);
+            }
+        }
+
+        self.db_pool.touch_transaction_logs(&polled).await?;
+
+        Ok(())
+    }
+
+    async fn apply_status(
+        &self,
+        signature: &Signature,
+        log: &TransactionLog,
+        status: solana_transaction_status::TransactionStatus,
+    ) -> Result<()> {
+        if let Some(err) = status.err {
+            info!("Transaction {} failed: {:?}", log.signature, err);
+            self.db_pool.update_transaction_status(
+                &log.signature,
+                "failed",
+                Some(status.slot),
+                None,
+                None,
+                Some(&format!("{:?}", err)),
+            ).await?;
+
+            // A failed durable transaction still advances its nonce
+            return self.release_nonce(log).await;
+        }
+
+        let next = match status.confirmation_status {
+            Some(TransactionConfirmationStatus::Finalized) => "finalized",
+            Some(TransactionConfirmationStatus::Confirmed) => "confirmed",
+            Some(TransactionConfirmationStatus::Processed) | None => "processed",
+        };
+
+        if next == log.status {
+            return Ok(());
+        }
+
+        // Fee and block time are only read once, when the transaction is final
+        let (block_time, fee) = if next == "finalized" {
+            let tx = self.rpc_service.get_transaction_logs(signature).await?;
+            (tx.block_time, Some(tx.fee))
+        } else {
+            (None, None)
+        };
+
+        debug!("Transaction {} moved {} -> {}", log.signature, log.status, next);
+
+        self.db_pool.update_transaction_status(
+            &log.signature,
+            next,
+            Some(status.slot),
+            block_time,
+            fee,
+            None,
+        ).await?;
+
+        if next == "finalized" {
+            self.release_nonce(log).await?;
+        }
+
+        Ok(())
+    }
+
+    /// A transaction the cluster has never seen is dead once its blockhash
+    /// can no longer be used, or for a durable transaction, once its nonce
+    /// account holds a different nonce.
+    async fn check_expiry(&self, signature: &Signature, log: &TransactionLog) -> Result<()> {
+        let Some(blockhash) = log.recent_blockhash.as_deref() else {
+            return Ok(());
+        };
+
+        let blockhash = Hash::from_str(blockhash)?;
+        let (valid, reason) = match log.nonce_account.as_deref() {
+            Some(nonce_account) => {
+                let current = self.rpc_service
+                    .get_nonce_blockhash(&Pubkey::from_str(nonce_account)?)
+                    .await?;
+                (current == blockhash, "Nonce advanced before the transaction was processed")
+            }
+            None => (
+                self.rpc_service.is_blockhash_valid(&blockhash).await?,
+                "Blockhash expired before the transaction was processed",
+            ),
+        };
+
+        if valid {
+            return Ok(());
+        }
+
+        // Landing advances the nonce too. If it landed after the status was
+        // read, the next poll records it instead.
+        let landed = self.rpc_service
+            .get_signature_statuses_with_history(&[*signature])
+            .await?
+            .into_iter()
+            .next()
+            .flatten();
+        if landed.is_some() {
+            debug!("Transaction {} landed while checking for expiry", log.signature);
+            return Ok(());
+        }
+
+        info!("Transaction {} expired without landing", log.signature);
+
+        self.db_pool.update_transaction_status(
+            &log.signature,
+            "expired",
+            None,
+            None,
+            None,
+            Some(reason),
+        ).await?;
+
+        self.release_nonce(log).await
+    }
+
+    /// Frees the nonce account a durable transaction reserved once the
+    /// transaction can no longer change state.
+    async fn release_nonce(&self, log: &TransactionLog) -> Result<()> {
+        if let Some(nonce_account) = log.nonce_account.as_deref() {
+            debug!("Releasing nonce account {} used by {}", nonce_account, log.signature);
+            self.db_pool.release_nonce_account(nonce_account).await?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,8 +1,13 @@
 use solana_sdk::{
     pubkey::Pubkey,
-    transaction::Transaction,
     instruction::Instruction,
     compute_budget::ComputeBudgetInstruction,
+    address_lookup_table::AddressLookupTableAccount,
+    message::{v0, Message, VersionedMessage},
+    signature::Signature,
+    system_instruction::{self, SystemInstruction},
+    system_program,
+    transaction::VersionedTransaction,
 };
 use anchor_client::{
     solana_sdk::{
@@ -13,21 +18,32 @@ use anchor_client::{
 use anyhow::{Result, Context};
 
 pub struct TransactionBuilder {
-    payer: Keypair,
+    payer: Pubkey,
     recent_blockhash: solana_sdk::hash::Hash,
     instructions: Vec<Instruction>,
     signers: Vec<Keypair>,
     priority_fee: Option<u64>,
+    compute_unit_limit: Option<u32>,
+    lookup_tables: Vec<AddressLookupTableAccount>,
+    durable_nonce: Option<(Pubkey, Pubkey)>,
 }
 
 impl TransactionBuilder {
     pub fn new(payer: Keypair, recent_blockhash: solana_sdk::hash::Hash) -> Self {
+        Self::with_fee_payer(payer.pubkey(), recent_blockhash).add_signer(payer)
+    }
+    
+    /// Starts a transaction whose fee payer signs elsewhere, e.g. in a wallet.
+    pub fn with_fee_payer(payer: Pubkey, recent_blockhash: solana_sdk::hash::Hash) -> Self {
         Self {
             payer,
             recent_blockhash,
             instructions: Vec::new(),
-            signers: vec![payer.clone()],
+            signers: Vec::new(),
             priority_fee: None,
+            compute_unit_limit: None,
+            lookup_tables: Vec::new(),
+            durable_nonce: None,
         }
     }
     
@@ -46,7 +62,26 @@ impl TransactionBuilder {
         self
     }
     
-    pub fn build(self) -> Result<Transaction> {
+    pub fn set_compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+    
+    /// Lookup tables the message may load accounts from. They are only used
+    /// when the resulting v0 message is smaller than the legacy one.
+    pub fn add_lookup_tables(mut self, tables: Vec<AddressLookupTableAccount>) -> Self {
+        self.lookup_tables.extend(tables);
+        self
+    }
+    
+    /// Advances `nonce_account` as the first instruction. The builder's
+    /// `recent_blockhash` must then be the nonce account's current value.
+    pub fn set_durable_nonce(mut self, nonce_account: Pubkey, authority: Pubkey) -> Self {
+        self.durable_nonce = Some((nonce_account, authority));
+        self
+    }
+    
+    pub fn build(self) -> Result<VersionedTransaction> {
         let mut instructions = self.instructions;
         
         // Add priority fee instruction if specified
@@ -55,13 +90,71 @@ impl TransactionBuilder {
             instructions.insert(0, priority_fee_ix);
         }
         
-        let mut transaction = Transaction::new_with_payer(
+        // Add compute unit limit instruction if specified
+        if let Some(units) = self.compute_unit_limit {
+            let compute_limit_ix = ComputeBudgetInstruction::set_compute_unit_limit(units);
+            instructions.insert(0, compute_limit_ix);
+        }
+        
+        // The runtime only recognizes a durable transaction by its first instruction
+        if let Some((nonce_account, authority)) = self.durable_nonce {
+            instructions.insert(0, system_instruction::advance_nonce_account(&nonce_account, &authority));
+        }
+        
+        let mut message = VersionedMessage::Legacy(Message::new_with_blockhash(
             &instructions,
-            Some(&self.payer.pubkey()),
-        );
+            Some(&self.payer),
+            &self.recent_blockhash,
+        ));
+        
+        if !self.lookup_tables.is_empty() {
+            let v0_message = VersionedMessage::V0(v0::Message::try_compile(
+                &self.payer,
+                &instructions,
+                &self.lookup_tables,
+                self.recent_blockhash,
+            ).context("Failed to compile v0 message")?);
+            
+            if v0_message.serialize().len() < message.serialize().len() {
+                message = v0_message;
+            }
+        }
         
-        transaction.sign(&self.signers, self.recent_blockhash);
+        // Signatures of signers not held here are left as defaults
+        let message_data = message.serialize();
+        let keys = message.static_account_keys();
+        let num_signers = message.header().num_required_signatures as usize;
+        let mut signatures = vec![Signature::default(); num_signers];
         
-        Ok(transaction)
+        for signer in &self.signers {
+            let index = keys[..num_signers]
+                .iter()
+                .position(|key| *key == signer.pubkey())
+                .context("Failed to sign transaction: keypair is not a required signer")?;
+            signatures[index] = signer.sign_message(&message_data);
+        }
+        
+        Ok(VersionedTransaction {
+            signatures,
+            message,
+        })
     }
-}
\ No newline at end of file
+}
+
+/// Returns the nonce account advanced by a durable transaction, or `None`
+/// when the message uses a regular blockhash.
+pub fn durable_nonce_account(message: &VersionedMessage) -> Option<Pubkey> {
+    let instruction = message.instructions().first()?;
+    let keys = message.static_account_keys();
+    
+    if keys.get(instruction.program_id_index as usize) != Some(&system_program::ID) {
+        return None;
+    }
+    
+    match bincode::deserialize::<SystemInstruction>(&instruction.data) {
+        Ok(SystemInstruction::AdvanceNonceAccount) => {
+            keys.get(*instruction.accounts.first()? as usize).copied()
+        }
+        _ => None,
+    }
+}
@@ -6,30 +6,72 @@ use anchor_client::{
         pubkey::Pubkey,
         signature::{Keypair, Signature},
         signer::Signer,
-        transaction::Transaction,
+        transaction::{TransactionVersion, VersionedTransaction},
+        nonce,
+        system_instruction,
         commitment_config::CommitmentConfig,
+        instruction::Instruction,
     },
 };
 use anchor_lang::prelude::AccountMeta;
 use anyhow::{Result, Context};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde_json::Value;
 use tracing::{info, warn, error};
 
 use crate::database::DatabasePool;
-use crate::services::rpc::RpcService;
-use crate::utils::anchor_client::AnchorClient;
+use crate::database::events::VaultEventFilter;
+use crate::services::event_hub::EventHub;
+use crate::services::policy::TransactionPolicy;
+use crate::services::rpc::{RpcService, TransactionStatus};
+use crate::services::sender::{SendConfig, TransactionSender};
+use crate::services::transaction::durable_nonce_account;
+use crate::utils::anchor_client::{AnchorClient, SignedTransaction};
+use crate::utils::idl::Idl;
 use crate::models::{
     requests::*,
     responses::*,
-    database::{Vault, VaultEvent},
+    database::{NonceAccount, TransactionLog, Vault},
+    onchain::{CollateralVault, OnchainAccount, OnchainVaultState, VaultAuthority},
 };
 
+/// Mints added to a newly created lookup table.
+const LOOKUP_TABLE_MINTS: i64 = 16;
+
+/// Addresses per extend transaction, keeping it well under the size limit.
+const LOOKUP_TABLE_EXTEND_CHUNK: usize = 20;
+
+/// A `/transactions/build` request the service refuses to build.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct BuildRejected(pub String);
+
+/// Requests the service can't carry out as things stand, as opposed to
+/// failures of the service itself.
+#[derive(Debug, thiserror::Error)]
+pub enum Unfulfillable {
+    #[error("Vaults hold different token mints")]
+    MintMismatch,
+    #[error("No address lookup table is configured")]
+    NoLookupTable,
+    #[error("No durable nonce account is available")]
+    NoncesExhausted,
+}
+
+/// A submitted transaction that isn't base64-encoded bincode.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}")]
+pub struct MalformedTransaction(pub &'static str);
+
 #[derive(Clone)]
 pub struct VaultService {
     db_pool: DatabasePool,
     rpc_service: RpcService,
     anchor_client: AnchorClient,
+    sender: TransactionSender,
+    policy: TransactionPolicy,
     admin_keypair: Keypair,
+    event_hub: EventHub,
 }
 
 impl VaultService {
@@ -38,6 +80,9 @@ impl VaultService {
         rpc_service: RpcService,
         program_id: String,
         admin_keypair_path: std::path::PathBuf,
+        idl: Idl,
+        send_config: SendConfig,
+        event_hub: EventHub,
     ) -> Result<Self> {
         let admin_keypair = Keypair::from_base58_string(
             &std::fs::read_to_string(admin_keypair_path)?
@@ -47,139 +92,29 @@ impl VaultService {
             program_id,
             admin_keypair.clone(),
             rpc_service.clone(),
+            idl,
         )?;
         
+        let sender = TransactionSender::new(rpc_service.clone(), send_config);
+        let policy = TransactionPolicy::new(anchor_client.program_id(), admin_keypair.pubkey());
+        
         Ok(Self {
             db_pool,
             rpc_service,
             anchor_client,
+            sender,
+            policy,
             admin_keypair,
+            event_hub,
         })
     }
     
-    pub async fn initialize_vault(
-        &self,
-        owner: &str,
-        token_mint: &str,
-    ) -> Result<InitializeVaultResult> {
-        let owner_pubkey = Pubkey::from_str(owner)?;
-        let token_mint_pubkey = Pubkey::from_str(token_mint)?;
-        
-        // Build transaction using Anchor client
-        let tx = self.anchor_client.build_initialize_vault_transaction(
-            owner_pubkey,
-            token_mint_pubkey,
-        ).await?;
-        
-        let signature = self.rpc_service.send_transaction(&tx).await?;
-        
-        // Wait for confirmation
-        self.rpc_service.confirm_transaction(&signature).await?;
-        
-        // Store vault in database
-        let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
-        
-        self.db_pool.store_vault(Vault {
-            id: uuid::Uuid::new_v4(),
-            owner: owner.to_string(),
-            vault_address: vault_pubkey.to_string(),
-            token_mint: token_mint.to_string(),
-            total_balance: 0,
-            locked_balance: 0,
-            available_balance: 0,
-            total_deposited: 0,
-            total_withdrawn: 0,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-        }).await?;
-        
-        Ok(InitializeVaultResult {
-            vault_address: vault_pubkey.to_string(),
-            transaction: bs58::encode(tx.message_data()).into_string(),
-            signature: signature.to_string(),
-        })
-    }
-    
-    pub async fn deposit_collateral(
-        &self,
-        owner: &str,
-        amount: u64,
-        user_token_account: &str,
-        priority_fee: Option<u64>,
-    ) -> Result<TransactionResult> {
-        let owner_pubkey = Pubkey::from_str(owner)?;
-        let user_token_account_pubkey = Pubkey::from_str(user_token_account)?;
-        
-        // Get vault PDA
-        let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
-        
-        // Build deposit transaction
-        let tx = self.anchor_client.build_deposit_transaction(
-            owner_pubkey,
-            vault_pubkey,
-            user_token_account_pubkey,
-            amount,
-            priority_fee,
-        ).await?;
-        
-        let signature = self.rpc_service.send_transaction(&tx).await?;
-        
-        // Log event
-        self.log_vault_event(
-            &owner,
-            "deposit",
-            &serde_json::json!({
-                "amount": amount,
-                "signature": signature.to_string(),
-            }),
-        ).await?;
-        
-        Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
-            signature: signature.to_string(),
-            estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
-        })
+    pub fn rpc_service(&self) -> &RpcService {
+        &self.rpc_service
     }
     
-    pub async fn withdraw_collateral(
-        &self,
-        owner: &str,
-        amount: u64,
-        user_token_account: &str,
-        priority_fee: Option<u64>,
-    ) -> Result<TransactionResult> {
-        let owner_pubkey = Pubkey::from_str(owner)?;
-        let user_token_account_pubkey = Pubkey::from_str(user_token_account)?;
-        
-        // Get vault PDA
-        let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
-        
-        // Build withdraw transaction
-        let tx = self.anchor_client.build_withdraw_transaction(
-            owner_pubkey,
-            vault_pubkey,
-            user_token_account_pubkey,
-            amount,
-            priority_fee,
-        ).await?;
-        
-        let signature = self.rpc_service.send_transaction(&tx).await?;
-        
-        // Log event
-        self.log_vault_event(
-            &owner,
-            "withdraw",
-            &serde_json::json!({
-                "amount": amount,
-                "signature": signature.to_string(),
-            }),
-        ).await?;
-        
-        Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
-            signature: signature.to_string(),
-            estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
-        })
+    pub fn program_id(&self) -> Pubkey {
+        self.anchor_client.program_id()
     }
     
     pub async fn lock_collateral(
@@ -188,6 +123,7 @@ impl VaultService {
         amount: u64,
         caller_program: &str,
         priority_fee: Option<u64>,
+        priority_level: Option<PriorityLevel>,
     ) -> Result<TransactionResult> {
         let owner_pubkey = Pubkey::from_str(owner)?;
         let caller_program_pubkey = Pubkey::from_str(caller_program)?;
@@ -195,45 +131,55 @@ impl VaultService {
         // Get vault PDA
         let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
         
-        // Build lock transaction
-        let tx = self.anchor_client.build_lock_collateral_transaction(
+        let priority_fee = self.resolve_priority_fee(
+            priority_fee,
+            priority_level,
+            &[vault_pubkey],
+        ).await?;
+        
+        let instruction = self.anchor_client.lock_collateral_instruction(
             vault_pubkey,
             caller_program_pubkey,
             amount,
-            priority_fee,
-        ).await?;
+        )?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        // Build lock transaction
+        let (tx, signature) = self.send_built(Some(owner), "lock", priority_fee, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
         
         // Update database
-        self.db_pool.update_vault_balances(
-            owner,
-            |vault| {
-                if vault.available_balance >= amount {
-                    vault.available_balance -= amount;
-                    vault.locked_balance += amount;
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Insufficient available balance"))
-                }
-            },
-        ).await?;
-        
-        // Log event
-        self.log_vault_event(
-            &owner,
-            "lock",
-            &serde_json::json!({
-                "amount": amount,
-                "caller_program": caller_program,
-                "signature": signature.to_string(),
-            }),
-        ).await?;
+        let recorded = async {
+            self.db_pool.update_vault_balances(
+                owner,
+                |vault| {
+                    if vault.available_balance >= amount {
+                        vault.available_balance -= amount;
+                        vault.locked_balance += amount;
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("Insufficient available balance"))
+                    }
+                },
+            ).await?;
+            
+            // Log event
+            self.log_vault_event(
+                &owner,
+                "lock",
+                &serde_json::json!({
+                    "amount": amount,
+                    "caller_program": caller_program,
+                    "signature": signature.to_string(),
+                }),
+            ).await
+        }.await;
+        log_unrecorded(&signature, "lock", recorded);
         
         Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
+            transaction: bs58::encode(tx.message.serialize()).into_string(),
             signature: signature.to_string(),
-            estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
+            estimated_fee: self.confirmed_fee(&tx).await,
         })
     }
     
@@ -243,6 +189,7 @@ impl VaultService {
         amount: u64,
         caller_program: &str,
         priority_fee: Option<u64>,
+        priority_level: Option<PriorityLevel>,
     ) -> Result<TransactionResult> {
         let owner_pubkey = Pubkey::from_str(owner)?;
         let caller_program_pubkey = Pubkey::from_str(caller_program)?;
@@ -250,45 +197,55 @@ impl VaultService {
         // Get vault PDA
         let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
         
-        // Build unlock transaction
-        let tx = self.anchor_client.build_unlock_collateral_transaction(
+        let priority_fee = self.resolve_priority_fee(
+            priority_fee,
+            priority_level,
+            &[vault_pubkey],
+        ).await?;
+        
+        let instruction = self.anchor_client.unlock_collateral_instruction(
             vault_pubkey,
             caller_program_pubkey,
             amount,
-            priority_fee,
-        ).await?;
+        )?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        // Build unlock transaction
+        let (tx, signature) = self.send_built(Some(owner), "unlock", priority_fee, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
         
         // Update database
-        self.db_pool.update_vault_balances(
-            owner,
-            |vault| {
-                if vault.locked_balance >= amount {
-                    vault.locked_balance -= amount;
-                    vault.available_balance += amount;
-                    Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Insufficient locked balance"))
-                }
-            },
-        ).await?;
-        
-        // Log event
-        self.log_vault_event(
-            &owner,
-            "unlock",
-            &serde_json::json!({
-                "amount": amount,
-                "caller_program": caller_program,
-                "signature": signature.to_string(),
-            }),
-        ).await?;
+        let recorded = async {
+            self.db_pool.update_vault_balances(
+                owner,
+                |vault| {
+                    if vault.locked_balance >= amount {
+                        vault.locked_balance -= amount;
+                        vault.available_balance += amount;
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("Insufficient locked balance"))
+                    }
+                },
+            ).await?;
+            
+            // Log event
+            self.log_vault_event(
+                &owner,
+                "unlock",
+                &serde_json::json!({
+                    "amount": amount,
+                    "caller_program": caller_program,
+                    "signature": signature.to_string(),
+                }),
+            ).await
+        }.await;
+        log_unrecorded(&signature, "unlock", recorded);
         
         Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
+            transaction: bs58::encode(tx.message.serialize()).into_string(),
             signature: signature.to_string(),
-            estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
+            estimated_fee: self.confirmed_fee(&tx).await,
         })
     }
     
@@ -299,109 +256,97 @@ impl VaultService {
         amount: u64,
         caller_program: &str,
         priority_fee: Option<u64>,
+        priority_level: Option<PriorityLevel>,
     ) -> Result<TransactionResult> {
         let from_owner_pubkey = Pubkey::from_str(from_owner)?;
         let to_owner_pubkey = Pubkey::from_str(to_owner)?;
         let caller_program_pubkey = Pubkey::from_str(caller_program)?;
         
-        // Get vault PDAs
-        let from_vault_pubkey = self.anchor_client.get_vault_pda(from_owner_pubkey)?;
-        let to_vault_pubkey = self.anchor_client.get_vault_pda(to_owner_pubkey)?;
+        // Both vaults must hold the same mint for a collateral transfer
+        let from_vault = self.db_pool.get_vault(from_owner).await?;
+        let to_vault = self.db_pool.get_vault(to_owner).await?;
+        if from_vault.token_mint != to_vault.token_mint {
+            return Err(Unfulfillable::MintMismatch.into());
+        }
+        let token_mint_pubkey = Pubkey::from_str(&from_vault.token_mint)?;
         
-        // Build transfer transaction
-        let tx = self.anchor_client.build_transfer_collateral_transaction(
-            from_vault_pubkey,
-            to_vault_pubkey,
-            caller_program_pubkey,
-            amount,
+        let priority_fee = self.resolve_priority_fee(
             priority_fee,
+            priority_level,
+            &[
+                self.anchor_client.get_vault_pda(from_owner_pubkey)?,
+                self.anchor_client.get_vault_pda(to_owner_pubkey)?,
+                self.anchor_client.get_vault_token_account(from_owner_pubkey, token_mint_pubkey)?,
+                self.anchor_client.get_vault_token_account(to_owner_pubkey, token_mint_pubkey)?,
+            ],
         ).await?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        let instruction = self.anchor_client.transfer_collateral_instruction(
+            from_owner_pubkey,
+            to_owner_pubkey,
+            token_mint_pubkey,
+            caller_program_pubkey,
+            amount,
+        )?;
+        
+        // Build transfer transaction
+        let (tx, signature) = self.send_built(Some(from_owner), "transfer", priority_fee, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
         
         // Update both vaults in database
-        self.db_pool.update_vault_balances(
-            from_owner,
-            |vault| {
-                if vault.available_balance >= amount {
-                    vault.available_balance -= amount;
-                    vault.total_balance -= amount;
+        let recorded = async {
+            self.db_pool.update_vault_balances(
+                from_owner,
+                |vault| {
+                    if vault.available_balance >= amount {
+                        vault.available_balance -= amount;
+                        vault.total_balance -= amount;
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("Insufficient available balance"))
+                    }
+                },
+            ).await?;
+            
+            self.db_pool.update_vault_balances(
+                to_owner,
+                |vault| {
+                    vault.available_balance += amount;
+                    vault.total_balance += amount;
                     Ok(())
-                } else {
-                    Err(anyhow::anyhow!("Insufficient available balance"))
-                }
-            },
-        ).await?;
-        
-        self.db_pool.update_vault_balances(
-            to_owner,
-            |vault| {
-                vault.available_balance += amount;
-                vault.total_balance += amount;
-                Ok(())
-            },
-        ).await?;
-        
-        // Log event
-        self.log_vault_event(
-            from_owner,
-            "transfer_out",
-            &serde_json::json!({
-                "amount": amount,
-                "to_owner": to_owner,
-                "caller_program": caller_program,
-                "signature": signature.to_string(),
-            }),
-        ).await?;
-        
-        self.log_vault_event(
-            to_owner,
-            "transfer_in",
-            &serde_json::json!({
-                "amount": amount,
-                "from_owner": from_owner,
-                "caller_program": caller_program,
-                "signature": signature.to_string(),
-            }),
-        ).await?;
-        
-        Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
-            signature: signature.to_string(),
-            estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
-        })
-    }
-    
-    pub async fn close_vault(&self, owner: &str) -> Result<TransactionResult> {
-        let owner_pubkey = Pubkey::from_str(owner)?;
-        
-        // Get vault PDA
-        let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
-        
-        // Build close vault transaction
-        let tx = self.anchor_client.build_close_vault_transaction(
-            owner_pubkey,
-            vault_pubkey,
-        ).await?;
-        
-        let signature = self.rpc_service.send_transaction(&tx).await?;
-        
-        // Delete vault from database
-        self.db_pool.delete_vault(owner).await?;
-        
-        // Log event
-        self.log_vault_event(
-            &owner,
-            "close",
-            &serde_json::json!({
-                "signature": signature.to_string(),
-            }),
-        ).await?;
+                },
+            ).await?;
+            
+            // Log event
+            self.log_vault_event(
+                from_owner,
+                "transfer_out",
+                &serde_json::json!({
+                    "amount": amount,
+                    "to_owner": to_owner,
+                    "caller_program": caller_program,
+                    "signature": signature.to_string(),
+                }),
+            ).await?;
+            
+            self.log_vault_event(
+                to_owner,
+                "transfer_in",
+                &serde_json::json!({
+                    "amount": amount,
+                    "from_owner": from_owner,
+                    "caller_program": caller_program,
+                    "signature": signature.to_string(),
+                }),
+            ).await
+        }.await;
+        log_unrecorded(&signature, "transfer", recorded);
         
         Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
+            transaction: bs58::encode(tx.message.serialize()).into_string(),
             signature: signature.to_string(),
-            estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
+            estimated_fee: self.confirmed_fee(&tx).await,
         })
     }
     
@@ -409,21 +354,29 @@ impl VaultService {
         &self,
         authorized_programs: &[String],
         priority_fee: Option<u64>,
+        priority_level: Option<PriorityLevel>,
     ) -> Result<TransactionResult> {
         let authorized_program_pubkeys: Vec<Pubkey> = authorized_programs
             .iter()
             .map(|p| Pubkey::from_str(p))
             .collect::<Result<Vec<_>, _>>()?;
         
-        let tx = self.anchor_client.build_initialize_authority_transaction(
-            &authorized_program_pubkeys,
+        let priority_fee = self.resolve_priority_fee(
             priority_fee,
+            priority_level,
+            &[self.anchor_client.get_authority_pda()?],
         ).await?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        let instruction = self.anchor_client.initialize_authority_instruction(
+            &authorized_program_pubkeys,
+        )?;
+        
+        let (tx, signature) = self.send_built(None, "initialize_authority", priority_fee, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
         
         Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
+            transaction: bs58::encode(tx.message.serialize()).into_string(),
             signature: signature.to_string(),
             estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
         })
@@ -433,18 +386,26 @@ impl VaultService {
         &self,
         program: &str,
         priority_fee: Option<u64>,
+        priority_level: Option<PriorityLevel>,
     ) -> Result<TransactionResult> {
         let program_pubkey = Pubkey::from_str(program)?;
         
-        let tx = self.anchor_client.build_add_authorized_program_transaction(
-            program_pubkey,
+        let priority_fee = self.resolve_priority_fee(
             priority_fee,
+            priority_level,
+            &[self.anchor_client.get_authority_pda()?],
         ).await?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        let instruction = self.anchor_client.add_authorized_program_instruction(
+            program_pubkey,
+        )?;
+        
+        let (tx, signature) = self.send_built(None, "add_authorized_program", priority_fee, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
         
         Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
+            transaction: bs58::encode(tx.message.serialize()).into_string(),
             signature: signature.to_string(),
             estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
         })
@@ -456,14 +417,16 @@ impl VaultService {
     ) -> Result<TransactionResult> {
         let program_pubkey = Pubkey::from_str(program)?;
         
-        let tx = self.anchor_client.build_remove_authorized_program_transaction(
+        let instruction = self.anchor_client.remove_authorized_program_instruction(
             program_pubkey,
-        ).await?;
+        )?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        let (tx, signature) = self.send_built(None, "remove_authorized_program", None, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
         
         Ok(TransactionResult {
-            transaction: bs58::encode(tx.message_data()).into_string(),
+            transaction: bs58::encode(tx.message.serialize()).into_string(),
             signature: signature.to_string(),
             estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
         })
@@ -485,71 +448,516 @@ impl VaultService {
         })
     }
     
+    pub async fn get_onchain_vault(&self, owner: &str) -> Result<OnchainVaultState> {
+        let owner_pubkey = Pubkey::from_str(owner)?;
+        let vault_pubkey = self.anchor_client.get_vault_pda(owner_pubkey)?;
+        
+        let data = self.rpc_service.get_account_data(&vault_pubkey).await
+            .context("Vault account not found on chain")?;
+        let vault = CollateralVault::decode(&data)?;
+        
+        let token_account_balance = self.rpc_service
+            .get_token_account_balance(&vault.token_account)
+            .await?;
+        
+        Ok(OnchainVaultState::new(vault_pubkey, &vault, token_account_balance))
+    }
+    
+    pub async fn get_onchain_authority(&self) -> Result<VaultAuthority> {
+        let authority_pubkey = self.anchor_client.get_authority_pda()?;
+        
+        let data = self.rpc_service.get_account_data(&authority_pubkey).await
+            .context("Vault authority account not found on chain")?;
+        
+        VaultAuthority::decode(&data)
+    }
+    
+    /// Creates the service's address lookup table, seeds it with the program
+    /// accounts and most common mints, and starts using it for new transactions.
+    pub async fn create_lookup_table(&self) -> Result<LookupTableResponse> {
+        let recent_slot = self.rpc_service.get_slot().await?;
+        let (instruction, address) = self.anchor_client.create_lookup_table_instruction(recent_slot);
+        
+        let (_, signature) = self.send_built(None, "create_lookup_table", None, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
+        
+        info!("Created address lookup table {}", address);
+        
+        // Keep it before extending, so a failed extension doesn't lose the table
+        self.db_pool
+            .insert_lookup_table(&address.to_string(), &self.admin_keypair.pubkey().to_string())
+            .await?;
+        
+        let mut addresses = self.anchor_client.lookup_table_defaults()?;
+        for mint in self.db_pool.list_common_mints(LOOKUP_TABLE_MINTS).await? {
+            addresses.push(Pubkey::from_str(&mint)?);
+        }
+        
+        let mut signatures = vec![signature.to_string()];
+        signatures.extend(self.append_to_lookup_table(address, addresses).await?);
+        
+        let table = self.anchor_client.load_lookup_table(address).await?;
+        
+        Ok(lookup_table_response(table.key, &table.addresses, signatures))
+    }
+    
+    /// Adds addresses missing from the active lookup table.
+    pub async fn extend_lookup_table(&self, addresses: &[String]) -> Result<LookupTableResponse> {
+        let table = self.anchor_client.lookup_table().await
+            .ok_or(Unfulfillable::NoLookupTable)?;
+        
+        let addresses = addresses
+            .iter()
+            .map(|a| Pubkey::from_str(a))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        let signatures = self.append_to_lookup_table(table.key, addresses).await?;
+        let table = self.anchor_client.load_lookup_table(table.key).await?;
+        
+        Ok(lookup_table_response(table.key, &table.addresses, signatures))
+    }
+    
+    pub async fn get_lookup_table(&self) -> Result<Option<LookupTableResponse>> {
+        Ok(self.anchor_client.lookup_table().await
+            .map(|table| lookup_table_response(table.key, &table.addresses, Vec::new())))
+    }
+    
+    /// Loads `LOOKUP_TABLE_ADDRESS` if set, or else the newest table the
+    /// service created.
+    pub async fn load_configured_lookup_table(&self, configured: Option<&str>) -> Result<()> {
+        let address = match configured {
+            Some(address) => Some(address.to_string()),
+            None => self.db_pool.latest_lookup_table().await?,
+        };
+        
+        if let Some(address) = address {
+            self.load_lookup_table(&address).await?;
+        }
+        
+        Ok(())
+    }
+    
+    async fn load_lookup_table(&self, address: &str) -> Result<()> {
+        let table = self.anchor_client.load_lookup_table(Pubkey::from_str(address)?).await?;
+        info!("Using address lookup table {} with {} addresses", table.key, table.addresses.len());
+        Ok(())
+    }
+    
+    /// Extends `lookup_table` with the addresses it doesn't hold yet, in
+    /// chunks small enough to fit a transaction.
+    async fn append_to_lookup_table(&self, lookup_table: Pubkey, addresses: Vec<Pubkey>) -> Result<Vec<String>> {
+        let existing = self.anchor_client.lookup_table().await
+            .filter(|table| table.key == lookup_table)
+            .map(|table| table.addresses)
+            .unwrap_or_default();
+        
+        let mut new_addresses: Vec<Pubkey> = Vec::new();
+        for address in addresses {
+            if !existing.contains(&address) && !new_addresses.contains(&address) {
+                new_addresses.push(address);
+            }
+        }
+        
+        let mut signatures = Vec::new();
+        for chunk in new_addresses.chunks(LOOKUP_TABLE_EXTEND_CHUNK) {
+            let instruction = self.anchor_client.extend_lookup_table_instruction(lookup_table, chunk.to_vec());
+            
+            let (_, signature) = self.send_built(None, "extend_lookup_table", None, |fee| {
+                self.anchor_client.build_transaction(instruction.clone(), fee)
+            }).await?;
+            
+            signatures.push(signature.to_string());
+        }
+        
+        Ok(signatures)
+    }
+    
+    /// Creates `count` nonce accounts with the admin keypair as authority and
+    /// adds them to the pool used for durable transactions.
+    pub async fn create_nonce_accounts(&self, count: usize) -> Result<Vec<String>> {
+        let lamports = self.rpc_service
+            .get_minimum_balance_for_rent_exemption(nonce::State::size())
+            .await?;
+        let authority = self.admin_keypair.pubkey().to_string();
+        let mut created = Vec::with_capacity(count);
+        
+        for _ in 0..count {
+            let nonce_keypair = Keypair::new();
+            let nonce_pubkey = nonce_keypair.pubkey();
+            let instructions = self.anchor_client.create_nonce_account_instructions(nonce_pubkey, lamports);
+            
+            self.send_built(None, "create_nonce_account", None, |fee| {
+                self.anchor_client.build_transaction_with_signers(
+                    instructions.clone(),
+                    std::slice::from_ref(&nonce_keypair),
+                    fee,
+                )
+            }).await?;
+            
+            self.db_pool.insert_nonce_account(&nonce_pubkey.to_string(), &authority).await?;
+            info!("Created nonce account {}", nonce_pubkey);
+            
+            created.push(nonce_pubkey.to_string());
+        }
+        
+        Ok(created)
+    }
+    
+    pub async fn list_nonce_accounts(&self) -> Result<Vec<NonceAccount>> {
+        self.db_pool.list_nonce_accounts().await
+    }
+    
+    /// Returns reservations nobody submitted a transaction for within
+    /// `max_age_secs` to the pool. The nonce is advanced first so anything
+    /// signed against the old value can no longer land.
+    pub async fn release_stale_nonces(&self, max_age_secs: i64) -> Result<usize> {
+        let stale = self.db_pool.list_stale_nonce_reservations(max_age_secs).await?;
+        
+        let mut released = 0;
+        
+        // One account that can't be advanced shouldn't keep the rest reserved
+        for account in &stale {
+            match self.release_stale_nonce(&account.address).await {
+                Ok(()) => {
+                    released += 1;
+                    info!("Released stale nonce reservation {}", account.address);
+                }
+                Err(e) => warn!("Failed to release stale nonce reservation {}: {:?}", account.address, e),
+            }
+        }
+        
+        Ok(released)
+    }
+    
+    async fn release_stale_nonce(&self, address: &str) -> Result<()> {
+        let nonce_pubkey = Pubkey::from_str(address)?;
+        let instruction = system_instruction::advance_nonce_account(
+            &nonce_pubkey,
+            &self.admin_keypair.pubkey(),
+        );
+        
+        self.send_built(None, "advance_nonce", None, |fee| {
+            self.anchor_client.build_transaction(instruction.clone(), fee)
+        }).await?;
+        
+        self.db_pool.release_nonce_account(address).await
+    }
+    
+    /// Builds a transaction for the caller's wallet to sign and hand back to
+    /// `submit_transaction`. The fee payer defaults to the instruction's
+    /// signer. Only instructions signed by the vault owner can be built; the
+    /// admin keypair signs nothing but the nonce advance of durable ones.
     pub async fn build_transaction(
         &self,
-        tx_type: &str,
-        parameters: &Value,
+        parameters: &BuildParameters,
+        fee_payer: Option<&str>,
+        durable: bool,
         priority_fee: Option<u64>,
-    ) -> Result<TransactionResult> {
-        match tx_type {
-            "initialize_vault" => {
-                let owner = parameters["owner"].as_str().unwrap();
-                let token_mint = parameters["token_mint"].as_str().unwrap();
+        priority_level: Option<PriorityLevel>,
+    ) -> Result<BuildTransactionResponse> {
+        let (instruction, signer) = self.build_instruction(parameters).await?;
+        
+        let fee_payer = match fee_payer {
+            Some(fee_payer) => Pubkey::from_str(fee_payer)?,
+            None => signer,
+        };
+        if fee_payer == self.admin_keypair.pubkey() {
+            return Err(BuildRejected("The service key can't pay for a wallet's transaction".to_string()).into());
+        }
+        
+        let writable_accounts: Vec<Pubkey> = instruction.accounts
+            .iter()
+            .filter(|a| a.is_writable && !a.is_signer)
+            .map(|a| a.pubkey)
+            .collect();
+        
+        let priority_fee = self.resolve_priority_fee(
+            priority_fee,
+            priority_level,
+            &writable_accounts,
+        ).await?;
+        
+        // Durable transactions reserve a nonce account until they settle or
+        // the reservation goes stale
+        let nonce_account = if durable {
+            let account = self.db_pool.reserve_nonce_account().await?
+                .ok_or(Unfulfillable::NoncesExhausted)?;
+            Some(Pubkey::from_str(&account.address)?)
+        } else {
+            None
+        };
+        
+        let built = self.anchor_client
+            .build_unsigned_transaction(instruction, fee_payer, nonce_account, priority_fee)
+            .await;
+        
+        let unsigned = match (built, nonce_account) {
+            (Ok(unsigned), _) => unsigned,
+            (Err(e), Some(nonce_account)) => {
+                self.db_pool.release_nonce_account(&nonce_account.to_string()).await?;
+                return Err(e);
+            }
+            (Err(e), None) => return Err(e),
+        };
+        let tx = &unsigned.transaction;
+        
+        // Track a new vault right away so its first deposit can be built before
+        // the indexer sees it; the reconciler reports it if it never lands
+        if let BuildParameters::InitializeVault { owner, token_mint } = parameters {
+            let owner_pubkey = Pubkey::from_str(owner)?;
+            self.db_pool.store_vault_if_missing(Vault {
+                id: uuid::Uuid::new_v4(),
+                owner: owner.clone(),
+                vault_address: self.anchor_client.get_vault_pda(owner_pubkey)?.to_string(),
+                token_mint: token_mint.clone(),
+                total_balance: 0,
+                locked_balance: 0,
+                available_balance: 0,
+                total_deposited: 0,
+                total_withdrawn: 0,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            }).await?;
+        }
+        
+        Ok(BuildTransactionResponse {
+            transaction: STANDARD.encode(bincode::serialize(tx)?),
+            version: match tx.version() {
+                TransactionVersion::Legacy(_) => "legacy".to_string(),
+                TransactionVersion::Number(n) => n.to_string(),
+            },
+            fee_payer: fee_payer.to_string(),
+            required_signers: unsigned.required_signers.iter().map(|s| s.to_string()).collect(),
+            recent_blockhash: tx.message.recent_blockhash().to_string(),
+            last_valid_block_height: unsigned.last_valid_block_height,
+            nonce_account: unsigned.nonce_account.map(|n| n.to_string()),
+            estimated_fee: self.rpc_service.get_fee_for_transaction(tx).await?,
+        })
+    }
+    
+    /// Returns the instruction for `parameters` and the key that has to sign it.
+    async fn build_instruction(&self, parameters: &BuildParameters) -> Result<(Instruction, Pubkey)> {
+        match parameters {
+            BuildParameters::InitializeVault { owner, token_mint } => {
                 let owner_pubkey = Pubkey::from_str(owner)?;
-                let token_mint_pubkey = Pubkey::from_str(token_mint)?;
+                let instruction = self.anchor_client.initialize_vault_instruction(
+                    owner_pubkey,
+                    Pubkey::from_str(token_mint)?,
+                )?;
                 
-                let tx = self.anchor_client.build_initialize_vault_transaction(
+                Ok((instruction, owner_pubkey))
+            }
+            BuildParameters::Deposit { owner, amount, user_token_account } => {
+                let owner_pubkey = Pubkey::from_str(owner)?;
+                let vault = self.db_pool.get_vault(owner).await?;
+                let instruction = self.anchor_client.deposit_instruction(
                     owner_pubkey,
-                    token_mint_pubkey,
-                ).await?;
+                    self.anchor_client.get_vault_pda(owner_pubkey)?,
+                    Pubkey::from_str(&vault.token_mint)?,
+                    Pubkey::from_str(user_token_account)?,
+                    *amount,
+                )?;
+                
+                Ok((instruction, owner_pubkey))
+            }
+            BuildParameters::Withdraw { owner, amount, user_token_account } => {
+                let owner_pubkey = Pubkey::from_str(owner)?;
+                let vault = self.db_pool.get_vault(owner).await?;
+                let instruction = self.anchor_client.withdraw_instruction(
+                    owner_pubkey,
+                    self.anchor_client.get_vault_pda(owner_pubkey)?,
+                    Pubkey::from_str(&vault.token_mint)?,
+                    Pubkey::from_str(user_token_account)?,
+                    *amount,
+                )?;
                 
-                Ok(TransactionResult {
-                    transaction: bs58::encode(tx.message_data()).into_string(),
-                    signature: "".to_string(), // Not signed yet
-                    estimated_fee: self.rpc_service.get_fee_for_transaction(&tx).await?,
-                })
+                Ok((instruction, owner_pubkey))
             }
-            // Add other transaction types...
-            _ => Err(anyhow::anyhow!("Unknown transaction type: {}", tx_type)),
+            BuildParameters::CloseVault { owner } => {
+                let owner_pubkey = Pubkey::from_str(owner)?;
+                let vault = self.db_pool.get_vault(owner).await?;
+                let instruction = self.anchor_client.close_vault_instruction(
+                    owner_pubkey,
+                    self.anchor_client.get_vault_pda(owner_pubkey)?,
+                    Pubkey::from_str(&vault.token_mint)?,
+                )?;
+                
+                Ok((instruction, owner_pubkey))
+            }
+            // These are signed by the service key, which never signs for a
+            // client-built transaction; they go through the server-sent routes
+            BuildParameters::LockCollateral { .. }
+            | BuildParameters::UnlockCollateral { .. }
+            | BuildParameters::TransferCollateral { .. }
+            | BuildParameters::InitializeAuthority { .. }
+            | BuildParameters::AddAuthorizedProgram { .. }
+            | BuildParameters::RemoveAuthorizedProgram { .. } => Err(BuildRejected(format!(
+                "{} is signed by the service and can't be built for a wallet",
+                parameters.tx_type(),
+            )).into()),
         }
     }
     
+    /// Accepts a base64 transaction as returned by `build_transaction`, once
+    /// every required signer has signed it. Anything the relay policy rejects
+    /// fails with a `PolicyViolation` and is never broadcast. With
+    /// `required_signer`, that key must be one of the signers.
     pub async fn submit_transaction(
         &self,
         signed_transaction: &str,
+        required_signer: Option<Pubkey>,
     ) -> Result<TransactionStatus> {
-        let tx_data = bs58::decode(signed_transaction).into_vec()?;
-        let tx = Transaction::try_from(&tx_data[..])?;
+        let tx_data = STANDARD.decode(signed_transaction)
+            .map_err(|_| MalformedTransaction("Transaction is not valid base64"))?;
+        let tx: VersionedTransaction = bincode::deserialize(&tx_data)
+            .map_err(|_| MalformedTransaction("Transaction could not be deserialized"))?;
+        
+        self.policy.check(&tx)?;
         
-        let signature = self.rpc_service.send_transaction(&tx).await?;
+        if let Some(signer) = required_signer {
+            self.policy.check_signed_by(&tx, &signer)?;
+        }
+        
+        let signature = self.send_and_track(&tx, None, "submitted").await?;
+        
+        // Client-signed transactions can't be rebuilt, so keep rebroadcasting
+        // the same bytes in the background until they land or expire
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            let outcome = sender.rebroadcast_until_settled(&tx, signature, None).await;
+            info!("Stopped rebroadcasting {}: {:?}", signature, outcome);
+        });
         
-        let status = self.rpc_service.get_transaction_status(&signature).await?;
+        // The tracker moves the row forward as the cluster confirms it
+        let log = self.db_pool.get_transaction_log(&signature.to_string()).await?
+            .context("Submitted transaction was not recorded")?;
         
-        Ok(status)
+        Ok(transaction_status_from_log(log))
     }
     
     pub async fn get_transaction_status(
         &self,
         signature: &str,
     ) -> Result<TransactionStatus> {
+        if let Some(log) = self.db_pool.get_transaction_log(signature).await? {
+            return Ok(transaction_status_from_log(log));
+        }
+        
         let sig = Signature::from_str(signature)?;
         self.rpc_service.get_transaction_status(&sig).await
     }
     
-    pub async fn stream_events(&self) -> impl futures::Stream<Item = Result<axum::response::sse::Event>> {
-        // Implement SSE stream for real-time vault events
-        use futures::stream::{self, StreamExt};
-        use tokio::time::{interval, Duration};
-        
-        let mut interval = interval(Duration::from_secs(1));
+    /// An explicit `priority_fee` wins; otherwise `priority_level` picks a
+    /// percentile from recent fees paid on the accounts the transaction writes.
+    async fn resolve_priority_fee(
+        &self,
+        priority_fee: Option<u64>,
+        priority_level: Option<PriorityLevel>,
+        writable_accounts: &[Pubkey],
+    ) -> Result<Option<u64>> {
+        if priority_fee.is_some() {
+            return Ok(priority_fee);
+        }
         
-        stream::unfold((), move |_| {
-            let interval = interval.tick();
-            async move {
-                interval.await;
-                Some((Ok(Event::default().data("ping")), ()))
+        match priority_level {
+            Some(level) => {
+                let estimate = self.rpc_service.estimate_priority_fees(writable_accounts).await?;
+                Ok(Some(estimate.for_level(level)))
             }
-        })
+            None => Ok(None),
+        }
+    }
+    
+    /// Records the transaction as `pending` before broadcasting it so the
+    /// tracker can follow it to a final state, even if the send itself fails.
+    async fn send_and_track(
+        &self,
+        tx: &VersionedTransaction,
+        vault_owner: Option<&str>,
+        transaction_type: &str,
+    ) -> Result<Signature> {
+        self.track_pending(tx, vault_owner, transaction_type).await?;
+        
+        match self.rpc_service.send_transaction(tx).await {
+            Ok(signature) => Ok(signature),
+            Err(e) => {
+                self.db_pool.update_transaction_status(
+                    &tx.signatures[0].to_string(),
+                    "failed",
+                    None,
+                    None,
+                    None,
+                    Some(&e.to_string()),
+                ).await?;
+                Err(e)
+            }
+        }
+    }
+    
+    /// Sends a server-signed transaction and waits for confirmation, rebuilding
+    /// it with a fresh blockhash and higher priority fee whenever an attempt
+    /// expires. Every attempt gets its own `transaction_logs` row.
+    async fn send_built<B, BFut>(
+        &self,
+        vault_owner: Option<&str>,
+        transaction_type: &str,
+        priority_fee: Option<u64>,
+        build: B,
+    ) -> Result<(VersionedTransaction, Signature)>
+    where
+        B: Fn(Option<u64>) -> BFut,
+        BFut: std::future::Future<Output = Result<SignedTransaction>>,
+    {
+        self.sender.send_with_rebuild(
+            priority_fee,
+            build,
+            |tx| async move { self.track_pending(&tx, vault_owner, transaction_type).await },
+            |signature, error| async move {
+                self.db_pool.update_transaction_status(
+                    &signature.to_string(),
+                    "failed",
+                    None,
+                    None,
+                    None,
+                    Some(&error),
+                ).await
+            },
+        ).await
+    }
+    
+    async fn track_pending(
+        &self,
+        tx: &VersionedTransaction,
+        vault_owner: Option<&str>,
+        transaction_type: &str,
+    ) -> Result<()> {
+        let signature = tx.signatures.first().copied().unwrap_or_default().to_string();
+        let nonce_account = durable_nonce_account(&tx.message).map(|n| n.to_string());
+        
+        self.db_pool.insert_pending_transaction(
+            &signature,
+            vault_owner,
+            transaction_type,
+            &tx.message.recent_blockhash().to_string(),
+            nonce_account.as_deref(),
+        ).await?;
+        
+        // The tracker releases the nonce account once this transaction settles
+        if let Some(nonce_account) = &nonce_account {
+            self.db_pool.attach_nonce_signature(nonce_account, &signature).await?;
+        }
+        
+        Ok(())
+    }
+    
+    /// Live vault events matching `filter`, resuming after `last_event_id`.
+    pub async fn stream_events(
+        &self,
+        filter: VaultEventFilter,
+        last_event_id: Option<i64>,
+    ) -> Result<impl futures::Stream<Item = Result<axum::response::sse::Event, std::convert::Infallible>>> {
+        self.event_hub.subscribe(filter, last_event_id).await
     }
     
     async fn log_vault_event(
@@ -558,16 +966,58 @@ impl VaultService {
         event_type: &str,
         data: &Value,
     ) -> Result<()> {
-        let event = VaultEvent {
-            id: uuid::Uuid::new_v4(),
-            vault_owner: owner.to_string(),
-            event_type: event_type.to_string(),
-            data: data.clone(),
-            created_at: chrono::Utc::now(),
-        };
-        
-        self.db_pool.store_vault_event(event).await?;
+        self.db_pool.store_vault_event(owner, event_type, data).await?;
         
         Ok(())
     }
-}
\ No newline at end of file
+    
+    /// Fee of a transaction that already confirmed, or 0 if it can't be
+    /// fetched; not worth failing the request over.
+    async fn confirmed_fee(&self, tx: &VersionedTransaction) -> u64 {
+        match self.rpc_service.get_fee_for_transaction(tx).await {
+            Ok(fee) => fee,
+            Err(e) => {
+                warn!("Failed to fetch fee of {}: {:?}", tx.signatures[0], e);
+                0
+            }
+        }
+    }
+}
+
+/// Logs bookkeeping that failed after a transaction confirmed. The chain
+/// already changed, so the request still succeeds and the reconciler brings
+/// the `vaults` row back in line.
+fn log_unrecorded(signature: &Signature, transaction_type: &str, recorded: Result<()>) {
+    if let Err(e) = recorded {
+        error!(
+            "{} transaction {} confirmed but could not be recorded, leaving it to the reconciler: {:?}",
+            transaction_type,
+            signature,
+            e,
+        );
+    }
+}
+
+fn transaction_status_from_log(log: TransactionLog) -> TransactionStatus {
+    let confirmation_status = match log.status.as_str() {
+        "processed" | "confirmed" | "finalized" => Some(log.status.clone()),
+        _ => None,
+    };
+    
+    TransactionStatus {
+        signature: log.signature,
+        status: log.status,
+        slot: log.slot.unwrap_or_default() as u64,
+        block_time: log.block_time,
+        confirmation_status,
+        error: log.error_message,
+    }
+}
+
+fn lookup_table_response(address: Pubkey, addresses: &[Pubkey], signatures: Vec<String>) -> LookupTableResponse {
+    LookupTableResponse {
+        address: address.to_string(),
+        addresses: addresses.iter().map(|a| a.to_string()).collect(),
+        signatures,
+    }
+}
@@ -1,40 +1,137 @@
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use anchor_client::{
-    Client, Cluster,
     solana_sdk::{
+        address_lookup_table::{
+            instruction::{create_lookup_table, extend_lookup_table},
+            state::AddressLookupTable,
+            AddressLookupTableAccount,
+        },
         pubkey::Pubkey,
-        signature::{Keypair, Signer},
-        transaction::Transaction,
-        commitment_config::CommitmentConfig,
-        instruction::Instruction,
+        signature::{Keypair, Signature, Signer},
+        transaction::VersionedTransaction,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        system_instruction,
     },
-    anchor_lang::AnchorDeserialize,
+    anchor_lang::{AnchorDeserialize, AnchorSerialize},
 };
-use anyhow::{Result, Context};
+use anyhow::{Result, Context, bail};
+use tokio::sync::RwLock;
+use crate::services::rpc::{RpcService, SimulationFailure};
 use crate::services::transaction::TransactionBuilder;
+use crate::utils::idl::{Idl, InstructionArgs};
+
+/// Highest compute unit limit a transaction may request.
+const MAX_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Headroom added on top of the simulated compute units.
+const COMPUTE_UNIT_MARGIN_PERCENT: u64 = 20;
+
+/// How long to wait for the slot after a lookup table's last extension.
+const LOOKUP_TABLE_WARMUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Instructions the backend builds; checked against the IDL at startup.
+pub const VAULT_INSTRUCTIONS: &[&str] = &[
+    "initialize_vault",
+    "initialize_authority",
+    "deposit",
+    "withdraw",
+    "lock_collateral",
+    "unlock_collateral",
+    "transfer_collateral",
+    "close_vault",
+    "add_authorized_program",
+    "remove_authorized_program",
+];
+
+#[derive(AnchorSerialize)]
+pub struct NoArgs {}
+
+impl InstructionArgs for NoArgs {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[];
+}
+
+#[derive(AnchorSerialize)]
+pub struct AmountArgs {
+    pub amount: u64,
+}
+
+impl InstructionArgs for AmountArgs {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[("amount", "u64")];
+}
+
+#[derive(AnchorSerialize)]
+pub struct InitializeAuthorityArgs {
+    pub authorized_programs: Vec<Pubkey>,
+}
+
+impl InstructionArgs for InitializeAuthorityArgs {
+    const FIELDS: &'static [(&'static str, &'static str)] =
+        &[("authorized_programs", "vec<publicKey>")];
+}
+
+#[derive(AnchorSerialize)]
+pub struct ProgramArgs {
+    pub program: Pubkey,
+}
+
+impl InstructionArgs for ProgramArgs {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[("program", "publicKey")];
+}
+
+/// A transaction built for a wallet to sign, with what it needs to track expiry.
+pub struct UnsignedTransaction {
+    pub transaction: VersionedTransaction,
+    /// `None` for durable transactions, which don't expire with a blockhash.
+    pub last_valid_block_height: Option<u64>,
+    pub nonce_account: Option<Pubkey>,
+    /// Signers whose signatures are still missing.
+    pub required_signers: Vec<Pubkey>,
+}
+
+/// A transaction the admin keypair signed and pays for, with the last block
+/// height at which its blockhash can still land.
+pub struct SignedTransaction {
+    pub transaction: VersionedTransaction,
+    pub last_valid_block_height: u64,
+}
 
 #[derive(Clone)]
 pub struct AnchorClient {
     program_id: Pubkey,
     admin_keypair: Keypair,
-    rpc_url: String,
+    rpc_service: RpcService,
+    idl: Idl,
+    lookup_table: Arc<RwLock<Option<AddressLookupTableAccount>>>,
 }
 
 impl AnchorClient {
     pub fn new(
         program_id: String,
         admin_keypair: Keypair,
-        rpc_url: String,
+        rpc_service: RpcService,
+        idl: Idl,
     ) -> Result<Self> {
         let program_id = Pubkey::from_str(&program_id)?;
         
+        idl.require_instructions(VAULT_INSTRUCTIONS)
+            .context("Program IDL does not match the vault instruction set")?;
+        
         Ok(Self {
             program_id,
             admin_keypair,
-            rpc_url,
+            rpc_service,
+            idl,
+            lookup_table: Arc::new(RwLock::new(None)),
         })
     }
     
+    pub fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+    
     pub fn get_vault_pda(&self, owner: Pubkey) -> Result<Pubkey> {
         let (vault_pda, _bump) = Pubkey::find_program_address(
             &[b"vault", owner.as_ref()],
@@ -53,34 +150,28 @@ impl AnchorClient {
         Ok(authority_pda)
     }
     
-    pub async fn build_initialize_vault_transaction(
+    pub fn get_vault_token_account(&self, owner: Pubkey, token_mint: Pubkey) -> Result<Pubkey> {
+        let vault_pda = self.get_vault_pda(owner)?;
+        
+        Ok(spl_associated_token_account::get_associated_token_address(
+            &vault_pda,
+            &token_mint,
+        ))
+    }
+    
+    pub fn initialize_vault_instruction(
         &self,
         owner: Pubkey,
         token_mint: Pubkey,
-    ) -> Result<Transaction> {
-        let (vault_pda, vault_bump) = Pubkey::find_program_address(
-            &[b"vault", owner.as_ref()],
-            &self.program_id,
-        );
-        
-        let (vault_token_account, _) = Pubkey::find_program_address(
-            &[
-                vault_pda.as_ref(),
-                &anchor_spl::token::ID.as_ref(),
-                token_mint.as_ref(),
-            ],
-            &spl_associated_token_account::id(),
-        );
+    ) -> Result<Instruction> {
+        let vault_pda = self.get_vault_pda(owner)?;
+        let vault_token_account = self.get_vault_token_account(owner, token_mint)?;
         
         let user_token_account = spl_associated_token_account::get_associated_token_address(
             &owner,
             &token_mint,
         );
         
-        let instruction_data = vec![
-            0, // discriminator for initialize_vault
-        ];
-        
         let accounts = vec![
             AccountMeta::new(owner, true),
             AccountMeta::new_readonly(token_mint, false),
@@ -92,94 +183,457 @@ impl AnchorClient {
             AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
         ];
         
-        let instruction = Instruction::new_with_bytes(
+        self.idl.encode_instruction(
             self.program_id,
-            &instruction_data,
+            "initialize_vault",
+            &NoArgs {},
             accounts,
-        );
+        )
+    }
+    
+    pub fn initialize_authority_instruction(
+        &self,
+        authorized_programs: &[Pubkey],
+    ) -> Result<Instruction> {
+        let authority_pda = self.get_authority_pda()?;
         
-        let client = Client::new(
-            Cluster::Custom(self.rpc_url.clone(), self.rpc_url.clone()),
-            &self.admin_keypair,
-        );
+        let accounts = vec![
+            AccountMeta::new(self.admin_keypair.pubkey(), true),
+            AccountMeta::new(authority_pda, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
+        ];
         
-        let mut builder = TransactionBuilder::new(
-            self.admin_keypair.clone(),
-            client.get_latest_blockhash()?,
-        );
+        self.idl.encode_instruction(
+            self.program_id,
+            "initialize_authority",
+            &InitializeAuthorityArgs {
+                authorized_programs: authorized_programs.to_vec(),
+            },
+            accounts,
+        )
+    }
+    
+    pub fn deposit_instruction(
+        &self,
+        owner: Pubkey,
+        vault: Pubkey,
+        token_mint: Pubkey,
+        user_token_account: Pubkey,
+        amount: u64,
+    ) -> Result<Instruction> {
+        let vault_token_account = self.get_vault_token_account(owner, token_mint)?;
         
-        let tx = builder
-            .add_instruction(instruction)
-            .build()?;
+        let accounts = vec![
+            AccountMeta::new(owner, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_mint, false),
+            AccountMeta::new(user_token_account, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ];
         
-        Ok(tx)
+        self.idl.encode_instruction(
+            self.program_id,
+            "deposit",
+            &AmountArgs { amount },
+            accounts,
+        )
     }
     
-    pub async fn build_deposit_transaction(
+    pub fn withdraw_instruction(
         &self,
         owner: Pubkey,
         vault: Pubkey,
+        token_mint: Pubkey,
         user_token_account: Pubkey,
         amount: u64,
-        priority_fee: Option<u64>,
-    ) -> Result<Transaction> {
-        let (vault_pda, _) = Pubkey::find_program_address(
-            &[b"vault", owner.as_ref()],
-            &self.program_id,
-        );
+    ) -> Result<Instruction> {
+        let vault_token_account = self.get_vault_token_account(owner, token_mint)?;
         
-        let vault_token_account = spl_associated_token_account::get_associated_token_address(
-            &vault_pda,
-            &token_mint,
-        );
+        let accounts = vec![
+            AccountMeta::new(owner, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_mint, false),
+            AccountMeta::new(user_token_account, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
+        ];
+        
+        self.idl.encode_instruction(
+            self.program_id,
+            "withdraw",
+            &AmountArgs { amount },
+            accounts,
+        )
+    }
+    
+    pub fn lock_collateral_instruction(
+        &self,
+        vault: Pubkey,
+        caller_program: Pubkey,
+        amount: u64,
+    ) -> Result<Instruction> {
+        let authority_pda = self.get_authority_pda()?;
+        
+        let accounts = vec![
+            AccountMeta::new_readonly(self.admin_keypair.pubkey(), true),
+            AccountMeta::new_readonly(authority_pda, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(caller_program, false),
+        ];
+        
+        self.idl.encode_instruction(
+            self.program_id,
+            "lock_collateral",
+            &AmountArgs { amount },
+            accounts,
+        )
+    }
+    
+    pub fn unlock_collateral_instruction(
+        &self,
+        vault: Pubkey,
+        caller_program: Pubkey,
+        amount: u64,
+    ) -> Result<Instruction> {
+        let authority_pda = self.get_authority_pda()?;
+        
+        let accounts = vec![
+            AccountMeta::new_readonly(self.admin_keypair.pubkey(), true),
+            AccountMeta::new_readonly(authority_pda, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(caller_program, false),
+        ];
         
-        let instruction_data = vec![
-            2, // discriminator for deposit
-            (amount >> 0) as u8,
-            (amount >> 8) as u8,
-            (amount >> 16) as u8,
-            (amount >> 24) as u8,
-            (amount >> 32) as u8,
-            (amount >> 40) as u8,
-            (amount >> 48) as u8,
-            (amount >> 56) as u8,
+        self.idl.encode_instruction(
+            self.program_id,
+            "unlock_collateral",
+            &AmountArgs { amount },
+            accounts,
+        )
+    }
+    
+    pub fn transfer_collateral_instruction(
+        &self,
+        from_owner: Pubkey,
+        to_owner: Pubkey,
+        token_mint: Pubkey,
+        caller_program: Pubkey,
+        amount: u64,
+    ) -> Result<Instruction> {
+        let authority_pda = self.get_authority_pda()?;
+        let from_vault = self.get_vault_pda(from_owner)?;
+        let to_vault = self.get_vault_pda(to_owner)?;
+        let from_vault_token_account = self.get_vault_token_account(from_owner, token_mint)?;
+        let to_vault_token_account = self.get_vault_token_account(to_owner, token_mint)?;
+        
+        let accounts = vec![
+            AccountMeta::new_readonly(self.admin_keypair.pubkey(), true),
+            AccountMeta::new_readonly(authority_pda, false),
+            AccountMeta::new(from_vault, false),
+            AccountMeta::new(to_vault, false),
+            AccountMeta::new(from_vault_token_account, false),
+            AccountMeta::new(to_vault_token_account, false),
+            AccountMeta::new_readonly(caller_program, false),
+            AccountMeta::new_readonly(anchor_spl::token::ID, false),
         ];
         
+        self.idl.encode_instruction(
+            self.program_id,
+            "transfer_collateral",
+            &AmountArgs { amount },
+            accounts,
+        )
+    }
+    
+    pub fn close_vault_instruction(
+        &self,
+        owner: Pubkey,
+        vault: Pubkey,
+        token_mint: Pubkey,
+    ) -> Result<Instruction> {
+        let vault_token_account = self.get_vault_token_account(owner, token_mint)?;
+        
+        // Rent from both the vault and its token account is returned to the owner
         let accounts = vec![
             AccountMeta::new(owner, true),
-            AccountMeta::new(vault_pda, false),
-            AccountMeta::new_readonly(token_mint, false),
-            AccountMeta::new(user_token_account, false),
+            AccountMeta::new(vault, false),
             AccountMeta::new(vault_token_account, false),
             AccountMeta::new_readonly(anchor_spl::token::ID, false),
+            AccountMeta::new_readonly(solana_sdk::system_program::ID, false),
         ];
         
-        let instruction = Instruction::new_with_bytes(
+        self.idl.encode_instruction(
             self.program_id,
-            &instruction_data,
+            "close_vault",
+            &NoArgs {},
             accounts,
-        );
+        )
+    }
+    
+    pub fn add_authorized_program_instruction(
+        &self,
+        program: Pubkey,
+    ) -> Result<Instruction> {
+        let authority_pda = self.get_authority_pda()?;
         
-        let client = Client::new(
-            Cluster::Custom(self.rpc_url.clone(), self.rpc_url.clone()),
-            &self.admin_keypair,
-        );
+        let accounts = vec![
+            AccountMeta::new_readonly(self.admin_keypair.pubkey(), true),
+            AccountMeta::new(authority_pda, false),
+        ];
         
-        let mut builder = TransactionBuilder::new(
-            self.admin_keypair.clone(),
-            client.get_latest_blockhash()?,
-        );
+        self.idl.encode_instruction(
+            self.program_id,
+            "add_authorized_program",
+            &ProgramArgs { program },
+            accounts,
+        )
+    }
+    
+    pub fn remove_authorized_program_instruction(
+        &self,
+        program: Pubkey,
+    ) -> Result<Instruction> {
+        let authority_pda = self.get_authority_pda()?;
         
-        if let Some(fee) = priority_fee {
-            builder = builder.set_priority_fee(fee);
+        let accounts = vec![
+            AccountMeta::new_readonly(self.admin_keypair.pubkey(), true),
+            AccountMeta::new(authority_pda, false),
+        ];
+        
+        self.idl.encode_instruction(
+            self.program_id,
+            "remove_authorized_program",
+            &ProgramArgs { program },
+            accounts,
+        )
+    }
+    
+    /// Accounts every vault transaction references; these seed a new lookup table.
+    pub fn lookup_table_defaults(&self) -> Result<Vec<Pubkey>> {
+        Ok(vec![
+            self.program_id,
+            self.get_authority_pda()?,
+            anchor_spl::token::ID,
+            spl_associated_token_account::ID,
+            solana_sdk::system_program::ID,
+        ])
+    }
+    
+    /// Returns the instruction creating a lookup table owned by the admin
+    /// keypair, and the table's address.
+    pub fn create_lookup_table_instruction(&self, recent_slot: u64) -> (Instruction, Pubkey) {
+        let admin = self.admin_keypair.pubkey();
+        create_lookup_table(admin, admin, recent_slot)
+    }
+    
+    pub fn extend_lookup_table_instruction(
+        &self,
+        lookup_table: Pubkey,
+        addresses: Vec<Pubkey>,
+    ) -> Instruction {
+        let admin = self.admin_keypair.pubkey();
+        extend_lookup_table(lookup_table, admin, Some(admin), addresses)
+    }
+    
+    /// Fetches a lookup table and uses it for every transaction built from
+    /// now on. Addresses extended in a slot can only be looked up from the
+    /// next one, so this waits that slot out after a recent extension.
+    pub async fn load_lookup_table(&self, address: Pubkey) -> Result<AddressLookupTableAccount> {
+        let data = self.rpc_service.get_account_data(&address).await
+            .context("Lookup table account not found on chain")?;
+        let table = AddressLookupTable::deserialize(&data)
+            .context("Account is not an address lookup table")?;
+        
+        self.rpc_service
+            .wait_past_slot(table.meta.last_extended_slot, LOOKUP_TABLE_WARMUP_TIMEOUT)
+            .await?;
+        
+        let account = AddressLookupTableAccount {
+            key: address,
+            addresses: table.addresses.to_vec(),
+        };
+        
+        *self.lookup_table.write().await = Some(account.clone());
+        
+        Ok(account)
+    }
+    
+    pub async fn lookup_table(&self) -> Option<AddressLookupTableAccount> {
+        self.lookup_table.read().await.clone()
+    }
+    
+    /// Instructions creating a nonce account with the admin keypair as its
+    /// authority. The new account's keypair has to sign the transaction.
+    pub fn create_nonce_account_instructions(&self, nonce_account: Pubkey, lamports: u64) -> Vec<Instruction> {
+        let admin = self.admin_keypair.pubkey();
+        system_instruction::create_nonce_account(&admin, &nonce_account, &admin, lamports)
+    }
+    
+    /// Builds a transaction paid for and signed by the admin keypair.
+    pub async fn build_transaction(
+        &self,
+        instruction: Instruction,
+        priority_fee: Option<u64>,
+    ) -> Result<SignedTransaction> {
+        self.build_transaction_with_signers(vec![instruction], &[], priority_fee).await
+    }
+    
+    /// Like `build_transaction`, for several instructions and signers besides
+    /// the admin keypair.
+    pub async fn build_transaction_with_signers(
+        &self,
+        instructions: Vec<Instruction>,
+        signers: &[Keypair],
+        priority_fee: Option<u64>,
+    ) -> Result<SignedTransaction> {
+        let (recent_blockhash, last_valid_block_height) = self.rpc_service.get_latest_blockhash_with_height().await?;
+        
+        let transaction = self.simulate_and_build(
+            instructions,
+            signers,
+            self.admin_keypair.pubkey(),
+            recent_blockhash,
+            None,
+            priority_fee,
+        ).await?;
+        
+        Ok(SignedTransaction {
+            transaction,
+            last_valid_block_height,
+        })
+    }
+    
+    /// Builds a transaction for a wallet to sign. The admin keypair neither
+    /// pays for it nor signs its instruction; it only signs as authority of
+    /// the durable nonce. Every other signature slot is left empty.
+    ///
+    /// With `durable_nonce`, the transaction uses the nonce account's value
+    /// instead of a blockhash and stays valid until the nonce is advanced.
+    pub async fn build_unsigned_transaction(
+        &self,
+        instruction: Instruction,
+        fee_payer: Pubkey,
+        durable_nonce: Option<Pubkey>,
+        priority_fee: Option<u64>,
+    ) -> Result<UnsignedTransaction> {
+        let admin = self.admin_keypair.pubkey();
+        if fee_payer == admin {
+            bail!("The service key can't pay for a wallet's transaction");
+        }
+        if instruction.accounts.iter().any(|a| a.is_signer && a.pubkey == admin) {
+            bail!("The service key can't sign a wallet's transaction");
         }
         
-        let tx = builder
-            .add_instruction(instruction)
+        let (recent_blockhash, last_valid_block_height) = match durable_nonce {
+            Some(nonce_account) => (self.rpc_service.get_nonce_blockhash(&nonce_account).await?, None),
+            None => {
+                let (blockhash, height) = self.rpc_service.get_latest_blockhash_with_height().await?;
+                (blockhash, Some(height))
+            }
+        };
+        
+        let transaction = self.simulate_and_build(
+            vec![instruction],
+            &[],
+            fee_payer,
+            recent_blockhash,
+            durable_nonce,
+            priority_fee,
+        ).await?;
+        
+        let required_signers = transaction.message.static_account_keys()
+            .iter()
+            .zip(&transaction.signatures)
+            .take(transaction.message.header().num_required_signatures as usize)
+            .filter(|(_, signature)| **signature == Signature::default())
+            .map(|(key, _)| *key)
+            .collect();
+        
+        Ok(UnsignedTransaction {
+            transaction,
+            last_valid_block_height,
+            nonce_account: durable_nonce,
+            required_signers,
+        })
+    }
+    
+    /// Simulates the instructions to size the compute unit limit, then builds
+    /// the final transaction. A failing simulation is returned as a
+    /// `SimulationFailure` carrying the decoded program error and logs.
+    async fn simulate_and_build(
+        &self,
+        instructions: Vec<Instruction>,
+        signers: &[Keypair],
+        fee_payer: Pubkey,
+        recent_blockhash: Hash,
+        durable_nonce: Option<Pubkey>,
+        priority_fee: Option<u64>,
+    ) -> Result<VersionedTransaction> {
+        let lookup_tables: Vec<_> = self.lookup_table.read().await.iter().cloned().collect();
+        
+        let builder = || {
+            let mut builder = self.transaction_builder(fee_payer, durable_nonce, recent_blockhash, priority_fee)
+                .add_lookup_tables(lookup_tables.clone());
+            for signer in signers {
+                builder = builder.add_signer(signer.insecure_clone());
+            }
+            for instruction in &instructions {
+                builder = builder.add_instruction(instruction.clone());
+            }
+            builder
+        };
+        
+        let probe = builder()
+            .set_compute_unit_limit(MAX_COMPUTE_UNITS)
             .build()?;
         
-        Ok(tx)
+        let simulation = self.rpc_service.simulate_transaction(&probe).await?;
+        
+        if let Some(err) = simulation.err {
+            return Err(SimulationFailure::new(
+                format!("{:?}", err),
+                simulation.logs.unwrap_or_default(),
+                simulation.units_consumed,
+                &self.idl,
+            ).into());
+        }
+        
+        let consumed = simulation.units_consumed.unwrap_or(MAX_COMPUTE_UNITS as u64);
+        let limit = (consumed * (100 + COMPUTE_UNIT_MARGIN_PERCENT) / 100)
+            .min(MAX_COMPUTE_UNITS as u64) as u32;
+        
+        builder()
+            .set_compute_unit_limit(limit)
+            .build()
     }
     
-    // Similar methods for other transactions (withdraw, lock, unlock, transfer, etc.)
-}
\ No newline at end of file
+    fn transaction_builder(
+        &self,
+        fee_payer: Pubkey,
+        durable_nonce: Option<Pubkey>,
+        recent_blockhash: Hash,
+        priority_fee: Option<u64>,
+    ) -> TransactionBuilder {
+        let admin = self.admin_keypair.pubkey();
+        let mut builder = TransactionBuilder::with_fee_payer(fee_payer, recent_blockhash);
+        
+        // Nonce accounts are created with the admin keypair as authority
+        if let Some(nonce_account) = durable_nonce {
+            builder = builder.set_durable_nonce(nonce_account, admin);
+        }
+        
+        // The admin signs what the server sends itself, and otherwise only as
+        // nonce authority; `build_unsigned_transaction` makes sure no other
+        // instruction of a wallet's transaction needs its signature
+        if fee_payer == admin || durable_nonce.is_some() {
+            builder = builder.add_signer(self.admin_keypair.clone());
+        }
+        
+        if let Some(fee) = priority_fee {
+            builder = builder.set_priority_fee(fee);
+        }
+        
+        builder
+    }
+}
@@ -1,25 +1,48 @@
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
+use solana_sdk::pubkey::ParsePubkeyError;
 use thiserror::Error;
 
+use crate::database::vaults::VaultNotFound;
+use crate::services::policy::PolicyViolation;
+use crate::services::rpc::{AccountNotFound, SimulationFailure};
+use crate::services::siws::SiwsError;
+use crate::services::vault::{BuildRejected, MalformedTransaction, Unfulfillable};
+
 #[derive(Debug, Error)]
 pub enum ApiError {
     #[error("Authentication failed")]
     Unauthorized,
     
+    #[error("Forbidden")]
+    Forbidden,
+    
+    /// Seconds until the caller may retry.
+    #[error("Rate limit exceeded")]
+    RateLimited(u64),
+    
     #[error("Invalid request: {0}")]
     BadRequest(String),
     
     #[error("Resource not found")]
     NotFound,
     
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    
+    #[error("Unprocessable: {0}")]
+    UnprocessableEntity(String),
+    
     #[error("Internal server error")]
     InternalServerError,
     
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+    
     #[error("Database error: {0}")]
     DatabaseError(#[from] sqlx::Error),
     
@@ -31,27 +54,123 @@ pub enum ApiError {
     
     #[error("Validation error: {0}")]
     ValidationError(#[from] validator::ValidationErrors),
+    
+    #[error(transparent)]
+    SimulationFailed(SimulationFailure),
+    
+    #[error(transparent)]
+    PolicyViolation(PolicyViolation),
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<SimulationFailure>() {
+            Ok(failure) => return ApiError::SimulationFailed(failure),
+            Err(err) => err,
+        };
+        
+        let err = match err.downcast::<PolicyViolation>() {
+            Ok(violation) => return ApiError::PolicyViolation(violation),
+            Err(err) => err,
+        };
+        
+        let err = match err.downcast::<BuildRejected>() {
+            Ok(rejected) => return ApiError::BadRequest(rejected.to_string()),
+            Err(err) => err,
+        };
+        
+        if let Some(malformed) = err.downcast_ref::<MalformedTransaction>() {
+            return ApiError::BadRequest(malformed.to_string());
+        }
+        
+        if let Some(unfulfillable) = err.downcast_ref::<Unfulfillable>() {
+            return match unfulfillable {
+                Unfulfillable::MintMismatch | Unfulfillable::NoLookupTable => {
+                    ApiError::UnprocessableEntity(unfulfillable.to_string())
+                }
+                Unfulfillable::NoncesExhausted => ApiError::ServiceUnavailable(unfulfillable.to_string()),
+            };
+        }
+        
+        if let Some(invalid) = err.downcast_ref::<ParsePubkeyError>() {
+            return ApiError::BadRequest(format!("Invalid public key: {}", invalid));
+        }
+        
+        if err.is::<VaultNotFound>() || err.is::<AccountNotFound>() {
+            tracing::debug!("Request failed: {:#}", err);
+            return ApiError::NotFound;
+        }
+        
+        match err.downcast::<SiwsError>() {
+            Ok(rejection) => {
+                tracing::debug!("Sign-in rejected: {}", rejection);
+                ApiError::Unauthorized
+            }
+            Err(err) => {
+                tracing::error!("Request failed: {:?}", err);
+                ApiError::InternalServerError
+            }
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            ApiError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized"),
-            ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
-            ApiError::NotFound => (StatusCode::NOT_FOUND, "Resource not found"),
-            ApiError::InternalServerError => (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
-            ApiError::DatabaseError(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
-            ApiError::SolanaError(msg) => (StatusCode::BAD_GATEWAY, msg.as_str()),
-            ApiError::TransactionError(msg) => (StatusCode::BAD_REQUEST, msg.as_str()),
-            ApiError::ValidationError(_) => (StatusCode::BAD_REQUEST, "Validation failed"),
-        };
+        match self {
+            ApiError::SimulationFailed(failure) => {
+                let body = Json(json!({
+                    "error": failure.to_string(),
+                    "code": StatusCode::UNPROCESSABLE_ENTITY.as_u16(),
+                    "program_error": failure.program_error,
+                    "logs": failure.logs,
+                    "units_consumed": failure.units_consumed,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                }));
+                
+                (StatusCode::UNPROCESSABLE_ENTITY, body).into_response()
+            }
+            ApiError::PolicyViolation(violation) => {
+                let body = Json(json!({
+                    "error": violation.to_string(),
+                    "code": StatusCode::FORBIDDEN.as_u16(),
+                    "violation": violation,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                }));
+                
+                (StatusCode::FORBIDDEN, body).into_response()
+            }
+            ApiError::RateLimited(retry_after) => {
+                let body = Json(json!({
+                    "error": "Rate limit exceeded",
+                    "code": StatusCode::TOO_MANY_REQUESTS.as_u16(),
+                    "retry_after": retry_after,
+                    "timestamp": chrono::Utc::now().to_rfc3339(),
+                }));
+                
+                (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after.to_string())], body).into_response()
+            }
+            ApiError::Unauthorized => error_response(StatusCode::UNAUTHORIZED, "Unauthorized"),
+            ApiError::Forbidden => error_response(StatusCode::FORBIDDEN, "Forbidden"),
+            ApiError::BadRequest(msg) => error_response(StatusCode::BAD_REQUEST, &msg),
+            ApiError::NotFound => error_response(StatusCode::NOT_FOUND, "Resource not found"),
+            ApiError::Conflict(msg) => error_response(StatusCode::CONFLICT, &msg),
+            ApiError::UnprocessableEntity(msg) => error_response(StatusCode::UNPROCESSABLE_ENTITY, &msg),
+            ApiError::InternalServerError => error_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error"),
+            ApiError::ServiceUnavailable(msg) => error_response(StatusCode::SERVICE_UNAVAILABLE, &msg),
+            ApiError::DatabaseError(_) => error_response(StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
+            ApiError::SolanaError(msg) => error_response(StatusCode::BAD_GATEWAY, &msg),
+            ApiError::TransactionError(msg) => error_response(StatusCode::BAD_REQUEST, &msg),
+            ApiError::ValidationError(_) => error_response(StatusCode::BAD_REQUEST, "Validation failed"),
+        }
+    }
+}
 
-        let body = Json(json!({
-            "error": error_message,
-            "code": status.as_u16(),
-            "timestamp": chrono::Utc::now().to_rfc3339(),
-        }));
+fn error_response(status: StatusCode, message: &str) -> Response {
+    let body = Json(json!({
+        "error": message,
+        "code": status.as_u16(),
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    }));
 
-        (status, body).into_response()
-    }
-}
\ No newline at end of file
+    (status, body).into_response()
+}
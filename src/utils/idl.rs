@@ -0,0 +1,389 @@
+use std::path::Path;
+use anchor_client::solana_sdk::{
+    hash::hash,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use anchor_lang::AnchorSerialize;
+use anyhow::{Result, Context, bail};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Subset of the Anchor IDL (0.29 format) needed to encode instructions.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Idl {
+    pub version: String,
+    pub name: String,
+    pub instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    pub accounts: Vec<IdlTypeDef>,
+    #[serde(default)]
+    pub events: Vec<IdlEvent>,
+    #[serde(default)]
+    pub errors: Vec<IdlErrorCode>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    pub accounts: Vec<IdlAccount>,
+    pub args: Vec<IdlField>,
+}
+
+/// One account of an instruction. Composite (`accounts`) and optional
+/// entries are only read so `Idl::from_json` can refuse them.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlAccount {
+    pub name: String,
+    #[serde(rename = "isMut", default)]
+    pub is_mut: bool,
+    #[serde(rename = "isSigner", default)]
+    pub is_signer: bool,
+    #[serde(rename = "isOptional", default)]
+    pub is_optional: bool,
+    #[serde(default)]
+    pub accounts: Option<Vec<Value>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: Value,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlTypeDef {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlEvent {
+    pub name: String,
+    pub fields: Vec<IdlField>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IdlErrorCode {
+    pub code: u32,
+    pub name: String,
+    #[serde(default)]
+    pub msg: Option<String>,
+}
+
+/// Typed instruction arguments, Borsh-encoded in IDL field order.
+///
+/// `FIELDS` lists `(name, type)` pairs using snake_case names and the IDL
+/// type notation (`u64`, `publicKey`, `vec<publicKey>`, ...).
+pub trait InstructionArgs: AnchorSerialize {
+    const FIELDS: &'static [(&'static str, &'static str)];
+}
+
+impl Idl {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read IDL from {}", path.display()))?;
+
+        Self::from_json(&raw)
+    }
+
+    /// Parses an IDL and refuses account layouts `encode_instruction` can't
+    /// produce: composite account groups would need flattening and optional
+    /// accounts a placeholder, so either would give a wrong account list.
+    pub fn from_json(raw: &str) -> Result<Self> {
+        let idl: Self = serde_json::from_str(raw).context("Failed to parse Anchor IDL")?;
+
+        for ix in &idl.instructions {
+            for account in &ix.accounts {
+                if account.accounts.is_some() {
+                    bail!(
+                        "Instruction `{}` uses composite account `{}`, which is not supported",
+                        ix.name,
+                        account.name,
+                    );
+                }
+                if account.is_optional {
+                    bail!(
+                        "Instruction `{}` has optional account `{}`, which is not supported",
+                        ix.name,
+                        account.name,
+                    );
+                }
+            }
+        }
+
+        Ok(idl)
+    }
+
+    pub fn instruction(&self, name: &str) -> Result<&IdlInstruction> {
+        self.instructions
+            .iter()
+            .find(|ix| to_snake_case(&ix.name) == name)
+            .with_context(|| format!("Instruction `{}` not found in IDL `{}`", name, self.name))
+    }
+
+    /// Fails if any of the given instructions is missing, so an IDL that no
+    /// longer matches our builders is rejected at startup.
+    pub fn require_instructions(&self, names: &[&str]) -> Result<()> {
+        for name in names {
+            self.instruction(name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Looks up a custom program error (code >= 6000) declared in the IDL.
+    pub fn error(&self, code: u32) -> Option<&IdlErrorCode> {
+        self.errors.iter().find(|e| e.code == code)
+    }
+
+    /// Builds an instruction after checking the arguments and account metas
+    /// against the IDL definition.
+    pub fn encode_instruction<T: InstructionArgs>(
+        &self,
+        program_id: Pubkey,
+        name: &str,
+        args: &T,
+        accounts: Vec<AccountMeta>,
+    ) -> Result<Instruction> {
+        let idl_ix = self.instruction(name)?;
+
+        validate_args::<T>(idl_ix)?;
+        validate_accounts(idl_ix, &accounts)?;
+
+        let mut data = instruction_discriminator(name).to_vec();
+        args.serialize(&mut data)
+            .with_context(|| format!("Failed to serialize args for `{}`", name))?;
+
+        Ok(Instruction::new_with_bytes(program_id, &data, accounts))
+    }
+}
+
+/// First 8 bytes of `sha256("global:<name>")`, as used by Anchor for dispatch.
+pub fn instruction_discriminator(name: &str) -> [u8; 8] {
+    sighash("global", name)
+}
+
+/// First 8 bytes of `sha256("account:<Name>")`, prefixed to every Anchor account.
+pub fn account_discriminator(name: &str) -> [u8; 8] {
+    sighash("account", name)
+}
+
+/// First 8 bytes of `sha256("event:<Name>")`, prefixed to `emit!` payloads.
+pub fn event_discriminator(name: &str) -> [u8; 8] {
+    sighash("event", name)
+}
+
+fn sighash(namespace: &str, name: &str) -> [u8; 8] {
+    let preimage = format!("{}:{}", namespace, name);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(preimage.as_bytes()).to_bytes()[..8]);
+    discriminator
+}
+
+fn validate_args<T: InstructionArgs>(idl_ix: &IdlInstruction) -> Result<()> {
+    if idl_ix.args.len() != T::FIELDS.len() {
+        bail!(
+            "Instruction `{}` expects {} args, builder provides {}",
+            idl_ix.name,
+            idl_ix.args.len(),
+            T::FIELDS.len(),
+        );
+    }
+
+    for (field, (name, ty)) in idl_ix.args.iter().zip(T::FIELDS) {
+        let idl_ty = type_name(&field.ty);
+        if to_snake_case(&field.name) != *name || idl_ty != *ty {
+            bail!(
+                "Instruction `{}` arg mismatch: IDL has `{}: {}`, builder has `{}: {}`",
+                idl_ix.name,
+                field.name,
+                idl_ty,
+                name,
+                ty,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_accounts(idl_ix: &IdlInstruction, accounts: &[AccountMeta]) -> Result<()> {
+    if idl_ix.accounts.len() != accounts.len() {
+        bail!(
+            "Instruction `{}` expects {} accounts, builder provides {}",
+            idl_ix.name,
+            idl_ix.accounts.len(),
+            accounts.len(),
+        );
+    }
+
+    for (index, (expected, meta)) in idl_ix.accounts.iter().zip(accounts).enumerate() {
+        if expected.is_mut != meta.is_writable || expected.is_signer != meta.is_signer {
+            bail!(
+                "Instruction `{}` account #{} (`{}`) mismatch: IDL mut={} signer={}, builder mut={} signer={}",
+                idl_ix.name,
+                index,
+                expected.name,
+                expected.is_mut,
+                expected.is_signer,
+                meta.is_writable,
+                meta.is_signer,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn type_name(ty: &Value) -> String {
+    match ty {
+        Value::String(s) => s.clone(),
+        Value::Object(map) => {
+            if let Some(inner) = map.get("vec") {
+                format!("vec<{}>", type_name(inner))
+            } else if let Some(inner) = map.get("option") {
+                format!("option<{}>", type_name(inner))
+            } else if let Some(Value::Array(parts)) = map.get("array") {
+                match parts.as_slice() {
+                    [inner, len] => format!("[{}; {}]", type_name(inner), len),
+                    _ => ty.to_string(),
+                }
+            } else if let Some(Value::String(defined)) = map.get("defined") {
+                defined.clone()
+            } else {
+                ty.to_string()
+            }
+        }
+        _ => ty.to_string(),
+    }
+}
+
+/// Anchor 0.29 IDLs use camelCase names while discriminators hash snake_case.
+fn to_snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.push(c.to_ascii_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorDeserialize;
+
+    #[derive(AnchorSerialize, AnchorDeserialize, Debug, PartialEq)]
+    struct LimitArgs {
+        amount: u64,
+        program: Pubkey,
+    }
+
+    impl InstructionArgs for LimitArgs {
+        const FIELDS: &'static [(&'static str, &'static str)] = &[("amount", "u64"), ("program", "publicKey")];
+    }
+
+    fn idl_with_accounts(accounts: &str) -> String {
+        format!(
+            r#"{{
+                "version": "0.1.0",
+                "name": "collateral_vault",
+                "instructions": [{{
+                    "name": "setLimit",
+                    "accounts": {},
+                    "args": [
+                        {{ "name": "amount", "type": "u64" }},
+                        {{ "name": "program", "type": "publicKey" }}
+                    ]
+                }}]
+            }}"#,
+            accounts,
+        )
+    }
+
+    fn simple_idl() -> Idl {
+        Idl::from_json(&idl_with_accounts(
+            r#"[
+                { "name": "vault", "isMut": true, "isSigner": false },
+                { "name": "authority", "isMut": false, "isSigner": true }
+            ]"#,
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn discriminators_match_anchor() {
+        assert_eq!(instruction_discriminator("deposit"), [242, 35, 198, 137, 82, 225, 242, 182]);
+        assert_eq!(instruction_discriminator("initialize_vault"), [48, 191, 163, 44, 71, 129, 63, 164]);
+        assert_eq!(account_discriminator("CollateralVault"), [19, 189, 95, 155, 100, 9, 159, 145]);
+    }
+
+    #[test]
+    fn converts_idl_names_to_snake_case() {
+        let cases = [
+            ("deposit", "deposit"),
+            ("initializeVault", "initialize_vault"),
+            ("addAuthorizedProgram", "add_authorized_program"),
+            ("close_vault", "close_vault"),
+        ];
+
+        for (name, expected) in cases {
+            assert_eq!(to_snake_case(name), expected);
+        }
+    }
+
+    #[test]
+    fn encodes_borsh_args_after_the_discriminator() {
+        let program_id = Pubkey::new_unique();
+        let args = LimitArgs { amount: 0x0102_0304_0506_0708, program: Pubkey::new_unique() };
+        let accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), true),
+        ];
+
+        let ix = simple_idl().encode_instruction(program_id, "set_limit", &args, accounts).unwrap();
+
+        assert_eq!(ix.program_id, program_id);
+        assert_eq!(ix.data[..8], instruction_discriminator("set_limit"));
+        assert_eq!(ix.data[8..16], [8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(ix.data[16..], args.program.to_bytes());
+        assert_eq!(LimitArgs::try_from_slice(&ix.data[8..]).unwrap(), args);
+    }
+
+    #[test]
+    fn rejects_accounts_that_disagree_with_the_idl() {
+        let args = LimitArgs { amount: 1, program: Pubkey::new_unique() };
+        let idl = simple_idl();
+
+        let missing = vec![AccountMeta::new(Pubkey::new_unique(), false)];
+        assert!(idl.encode_instruction(Pubkey::new_unique(), "set_limit", &args, missing).is_err());
+
+        let unsigned = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), false),
+        ];
+        assert!(idl.encode_instruction(Pubkey::new_unique(), "set_limit", &args, unsigned).is_err());
+    }
+
+    #[test]
+    fn refuses_composite_and_optional_accounts() {
+        let composite = idl_with_accounts(
+            r#"[{ "name": "common", "accounts": [{ "name": "vault", "isMut": true, "isSigner": false }] }]"#,
+        );
+        let optional = idl_with_accounts(
+            r#"[{ "name": "vault", "isMut": true, "isSigner": false, "isOptional": true }]"#,
+        );
+
+        for raw in [composite, optional] {
+            let err = Idl::from_json(&raw).unwrap_err();
+            assert!(err.to_string().contains("not supported"), "{}", err);
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub mod anchor_client;
+pub mod constants;
+pub mod error;
+pub mod idl;